@@ -3,7 +3,7 @@ rm -rf output/
 mkdir -p output/
 cargo build --release
 echo Running solver
-./target/release/ant_colony --batch "$@"
+./target/release/ant_colony solve --batch "$@"
 cd output
 echo Solver finished, converting dotfiles to images
 ls */*.dot | parallel dot {} -Tpng -o "{//}.png"
@@ -3,7 +3,7 @@ rm -rf output/
 mkdir -p output/
 cargo build --release
 echo Running solver
-./target/release/ant_colony --batch --record $@
+./target/release/ant_colony record --batch $@
 cd output
 echo Solver finished, converting dotfiles to images
 for dir in *
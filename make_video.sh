@@ -15,7 +15,7 @@ fi
 mkdir output/images
 cargo build --release
 echo Running solver
-./target/release/ant_colony --record $@ >| output/solution.dot
+./target/release/ant_colony record $@ >| output/solution.dot
 cd output
 echo Solver finished, converting dotfiles to images
 ls | grep ".dot" | parallel dot {} -Tpng -o ./images/{}.png
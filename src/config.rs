@@ -0,0 +1,340 @@
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::WorldState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TerminationMode {
+	Any, // stop as soon as one of the set criteria is met
+	All, // stop once every set criterion is met at the same time
+}
+
+// Every criterion left as `None` is ignored. With none set the run stops before the first iteration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminationCriteria {
+	pub max_iterations: Option<u32>,
+	pub time_limit: Option<Duration>,
+	pub target_length: Option<f64>, // met once the best solution is at most this long
+	pub stagnation_limit: Option<u32>, // iterations without improving the best solution
+	pub mode: TerminationMode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TerminationReason {
+	MaxIterations,
+	TimeLimit,
+	TargetLength,
+	Stagnation,
+	Interrupted, // the interrupt flag was raised, always stops the run regardless of the mode
+	StoppedByObserver,
+}
+
+impl std::fmt::Display for TerminationReason {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let description = match self {
+			TerminationReason::MaxIterations => "iteration limit reached",
+			TerminationReason::TimeLimit => "time limit reached",
+			TerminationReason::TargetLength => "target length reached",
+			TerminationReason::Stagnation => "no improvement within the stagnation limit",
+			TerminationReason::Interrupted => "interrupted",
+			TerminationReason::StoppedByObserver => "stopped by an observer",
+		};
+		return write!(f, "{}", description);
+	}
+}
+
+impl TerminationCriteria {
+	pub fn iterations(count: u32) -> Self {
+		return Self {
+			max_iterations: Some(count),
+			time_limit: None,
+			target_length: None,
+			stagnation_limit: None,
+			mode: TerminationMode::Any,
+		};
+	}
+
+	pub fn is_empty(&self) -> bool {
+		return self.max_iterations.is_none() && self.time_limit.is_none() && self.target_length.is_none() && self.stagnation_limit.is_none();
+	}
+
+	// returns the state of every set criterion, in declaration order
+	pub(crate) fn check(&self, world: &WorldState) -> Vec<(TerminationReason, bool)> {
+		let mut result = Vec::with_capacity(4);
+		if let Some(max_iterations) = self.max_iterations {
+			result.push((TerminationReason::MaxIterations, world.current_iteration >= max_iterations));
+		}
+		if let Some(time_limit) = self.time_limit {
+			result.push((TerminationReason::TimeLimit, world.elapsed >= time_limit));
+		}
+		if let Some(target_length) = self.target_length {
+			result.push((TerminationReason::TargetLength, world.best_solution_length <= target_length));
+		}
+		if let Some(stagnation_limit) = self.stagnation_limit {
+			result.push((TerminationReason::Stagnation, world.current_iteration - world.best_solution_iteration >= stagnation_limit));
+		}
+		return result;
+	}
+}
+
+
+// How pheromones get deposited after every iteration
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AlgorithmVariant {
+	AntSystem, // every ant deposits 1 / its tour length
+	ElitistAntSystem {
+		elitist_weight: f64, // the best tour so far additionally deposits elitist_weight / its length
+	},
+	RankBasedAntSystem {
+		ranked_ants: usize, // the best ranked_ants - 1 ants deposit (ranked_ants - rank) / tour length, the best tour so far ranked_ants / its length
+	},
+	MaxMinAntSystem {
+		// only the iteration best ant deposits, pheromones stay between max * min_max_ratio and max = 1 / ((1 - evaporation coefficient) * best length)
+		min_max_ratio: Option<f64>, // 1 / (2 * node count) if not set
+	},
+}
+
+impl AlgorithmVariant {
+	// short name without the parameters, as the command line spells it
+	pub fn name(&self) -> &'static str {
+		return match self {
+			AlgorithmVariant::AntSystem => "ant-system",
+			AlgorithmVariant::ElitistAntSystem { .. } => "elitist",
+			AlgorithmVariant::RankBasedAntSystem { .. } => "rank-based",
+			AlgorithmVariant::MaxMinAntSystem { .. } => "max-min",
+		};
+	}
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigData {
+	pub ant_count: usize,
+	pub random_choice_chance: f64, // in [0, 1)
+	pub pheromone_weight: f64,
+	pub heuristic_weight: f64,
+	pub termination: TerminationCriteria,
+	pub pheromone_evaporation_coefficient: f64, // share of pheromones kept after each iteration, in [0, 1]
+	pub seed: Option<u64>, // random if not set
+	pub variant: AlgorithmVariant,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+	NoAnts,
+	InvalidRandomChoiceChance(f64),
+	InvalidWeight { name: &'static str, value: f64 },
+	InvalidEvaporationCoefficient(f64),
+	NoTerminationCriteria,
+	InvalidTargetLength(f64),
+	InvalidTimeLimit(f64),
+	InvalidElitistWeight(f64),
+	InvalidRankedAntCount(usize),
+	InvalidMinMaxRatio(f64),
+}
+
+impl std::fmt::Display for ConfigError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		return match self {
+			ConfigError::NoAnts => write!(f, "the ant count has to be at least 1"),
+			ConfigError::InvalidRandomChoiceChance(value) => write!(f, "the random choice chance has to be at least 0 and below 1, got {}", value),
+			ConfigError::InvalidWeight { name, value } => write!(f, "the {} has to be a finite number of at least 0, got {}", name, value),
+			ConfigError::InvalidEvaporationCoefficient(value) => write!(f, "the evaporation coefficient has to be between 0 and 1, got {}", value),
+			ConfigError::NoTerminationCriteria => write!(f, "at least one termination criterion has to be set"),
+			ConfigError::InvalidTargetLength(value) => write!(f, "the target length has to be a number, got {}", value),
+			ConfigError::InvalidTimeLimit(value) => write!(f, "the time limit has to be a number of seconds of at least 0 that fits a duration, got {}", value),
+			ConfigError::InvalidElitistWeight(value) => write!(f, "the elitist weight has to be a finite number of at least 0, got {}", value),
+			ConfigError::InvalidRankedAntCount(value) => write!(f, "the number of ranked ants has to be at least 1, got {}", value),
+			ConfigError::InvalidMinMaxRatio(value) => write!(f, "the min-max pheromone ratio has to be above 0 and at most 1, got {}", value),
+		};
+	}
+}
+
+impl std::error::Error for ConfigError {}
+
+fn is_valid_weight(value: f64) -> bool {
+	return value.is_finite() && value >= 0.0;
+}
+
+// Ant System with the parameters recommended by Dorigo and Stützle, except for the ant count, which they set to the node count
+impl Default for ConfigData {
+	fn default() -> Self {
+		return Self {
+			ant_count: 30,
+			random_choice_chance: 0.0,
+			pheromone_weight: 1.0,
+			heuristic_weight: 2.0,
+			termination: TerminationCriteria::iterations(1000),
+			pheromone_evaporation_coefficient: 0.5,
+			seed: None,
+			variant: AlgorithmVariant::AntSystem,
+		};
+	}
+}
+
+impl ConfigData {
+	pub fn builder() -> ConfigBuilder {
+		return ConfigBuilder::new();
+	}
+
+	pub fn validate(&self) -> Result<(), ConfigError> {
+		if self.ant_count == 0 {
+			return Err(ConfigError::NoAnts);
+		}
+		if !(0.0..1.0).contains(&self.random_choice_chance) {
+			return Err(ConfigError::InvalidRandomChoiceChance(self.random_choice_chance));
+		}
+		if !is_valid_weight(self.pheromone_weight) {
+			return Err(ConfigError::InvalidWeight { name: "pheromone weight", value: self.pheromone_weight });
+		}
+		if !is_valid_weight(self.heuristic_weight) {
+			return Err(ConfigError::InvalidWeight { name: "heuristic weight", value: self.heuristic_weight });
+		}
+		if !(0.0..=1.0).contains(&self.pheromone_evaporation_coefficient) {
+			return Err(ConfigError::InvalidEvaporationCoefficient(self.pheromone_evaporation_coefficient));
+		}
+		if self.termination.is_empty() {
+			return Err(ConfigError::NoTerminationCriteria);
+		}
+		if let Some(target_length) = self.termination.target_length {
+			if target_length.is_nan() {
+				return Err(ConfigError::InvalidTargetLength(target_length));
+			}
+		}
+		match self.variant {
+			AlgorithmVariant::AntSystem => {},
+			AlgorithmVariant::ElitistAntSystem { elitist_weight } => {
+				if !is_valid_weight(elitist_weight) {
+					return Err(ConfigError::InvalidElitistWeight(elitist_weight));
+				}
+			},
+			AlgorithmVariant::RankBasedAntSystem { ranked_ants } => {
+				if ranked_ants == 0 {
+					return Err(ConfigError::InvalidRankedAntCount(ranked_ants));
+				}
+			},
+			AlgorithmVariant::MaxMinAntSystem { min_max_ratio } => {
+				if let Some(ratio) = min_max_ratio {
+					if !(ratio > 0.0 && ratio <= 1.0) {
+						return Err(ConfigError::InvalidMinMaxRatio(ratio));
+					}
+				}
+			},
+		}
+		return Ok(());
+	}
+}
+
+// Starts from `ConfigData::default()`, only `build` checks the values
+#[derive(Debug, Clone, Default)]
+pub struct ConfigBuilder {
+	config: ConfigData,
+	elitist_weight_from_ants: bool, // the elitist preset's weight follows the final ant count
+}
+
+impl ConfigBuilder {
+	pub fn new() -> Self {
+		return Self::default();
+	}
+
+	// same as `new`, for symmetry with the other presets
+	pub fn ant_system() -> Self {
+		return Self::new();
+	}
+
+	// elitist weight equal to the ant count, also when the count is set after the preset
+	pub fn elitist_ant_system() -> Self {
+		let mut result = Self::new();
+		result.config.variant = AlgorithmVariant::ElitistAntSystem { elitist_weight: result.config.ant_count as f64 };
+		result.elitist_weight_from_ants = true;
+		return result;
+	}
+
+	// six ranked ants and 90% of pheromones kept after each iteration
+	pub fn rank_based_ant_system() -> Self {
+		let mut result = Self::new();
+		result.config.variant = AlgorithmVariant::RankBasedAntSystem { ranked_ants: 6 };
+		result.config.pheromone_evaporation_coefficient = 0.9;
+		return result;
+	}
+
+	// 98% of pheromones kept after each iteration and the default min-max ratio
+	pub fn max_min_ant_system() -> Self {
+		let mut result = Self::new();
+		result.config.variant = AlgorithmVariant::MaxMinAntSystem { min_max_ratio: None };
+		result.config.pheromone_evaporation_coefficient = 0.98;
+		return result;
+	}
+
+	pub fn ant_count(mut self, ant_count: usize) -> Self {
+		self.config.ant_count = ant_count;
+		return self;
+	}
+
+	pub fn random_choice_chance(mut self, random_choice_chance: f64) -> Self {
+		self.config.random_choice_chance = random_choice_chance;
+		return self;
+	}
+
+	pub fn pheromone_weight(mut self, pheromone_weight: f64) -> Self {
+		self.config.pheromone_weight = pheromone_weight;
+		return self;
+	}
+
+	pub fn heuristic_weight(mut self, heuristic_weight: f64) -> Self {
+		self.config.heuristic_weight = heuristic_weight;
+		return self;
+	}
+
+	pub fn pheromone_evaporation_coefficient(mut self, coefficient: f64) -> Self {
+		self.config.pheromone_evaporation_coefficient = coefficient;
+		return self;
+	}
+
+	pub fn termination(mut self, termination: TerminationCriteria) -> Self {
+		self.config.termination = termination;
+		return self;
+	}
+
+	pub fn max_iterations(mut self, max_iterations: Option<u32>) -> Self {
+		self.config.termination.max_iterations = max_iterations;
+		return self;
+	}
+
+	pub fn time_limit(mut self, time_limit: Option<Duration>) -> Self {
+		self.config.termination.time_limit = time_limit;
+		return self;
+	}
+
+	pub fn target_length(mut self, target_length: Option<f64>) -> Self {
+		self.config.termination.target_length = target_length;
+		return self;
+	}
+
+	pub fn stagnation_limit(mut self, stagnation_limit: Option<u32>) -> Self {
+		self.config.termination.stagnation_limit = stagnation_limit;
+		return self;
+	}
+
+	pub fn termination_mode(mut self, mode: TerminationMode) -> Self {
+		self.config.termination.mode = mode;
+		return self;
+	}
+
+	pub fn seed(mut self, seed: Option<u64>) -> Self {
+		self.config.seed = seed;
+		return self;
+	}
+
+	pub fn variant(mut self, variant: AlgorithmVariant) -> Self {
+		self.config.variant = variant;
+		self.elitist_weight_from_ants = false;
+		return self;
+	}
+
+	pub fn build(mut self) -> Result<ConfigData, ConfigError> {
+		if self.elitist_weight_from_ants {
+			self.config.variant = AlgorithmVariant::ElitistAntSystem { elitist_weight: self.config.ant_count as f64 };
+		}
+		self.config.validate()?;
+		return Ok(self.config);
+	}
+}
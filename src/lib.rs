@@ -1,66 +1,238 @@
 #![allow(clippy::needless_return)]
 
-use std::collections::HashMap;
-use arrayvec::ArrayVec;
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 use itertools::Itertools;
 use rand::prelude::*;
+use rand_xoshiro::Xoshiro256PlusPlus; // same generator as `SmallRng`, but its state can be saved in checkpoints
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Eq, PartialOrd, Ord, serde::Deserialize, Copy)]
-pub struct GraphNode {
-	pub attraction_number: u8,
-	pub x: u8, // data does not contain negative or larger than 255 coordinates
-	pub y: u8
+mod config;
+mod records;
+mod search;
+pub mod stats;
+mod sweep;
+mod tour;
+mod tune;
+pub use config::{AlgorithmVariant, ConfigBuilder, ConfigData, ConfigError, TerminationCriteria, TerminationMode, TerminationReason};
+pub use records::{read_records_csv, read_records_file, read_records_json, write_records_csv, ExperimentRecord, RECORD_COLUMNS};
+pub use search::{hyperband, random_search, successive_halving, BudgetResource, Evaluation, SearchResult, SearchSettings};
+pub use stats::{run_batch, run_seed, BatchRunData, BatchSummary, RunRecord};
+pub use sweep::run_sweep;
+pub use tour::{evaluate_tour, read_tour, read_tour_file, Objective, TourEvaluation, TourFormat};
+pub use tune::{iterated_race, ParameterDomain, ParameterValue, RaceResult, RaceSettings, TunedConfiguration, TuningParameter};
+
+// Identifier of a node as written in the instance, anything that isn't an integer is kept as a label
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum NodeId {
+	Number(i64),
+	Label(String),
 }
 
-impl std::hash::Hash for GraphNode {
-	fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-		state.write_u8(self.attraction_number);
+impl NodeId {
+	// labels are quoted, so ones with spaces or punctuation are still valid graphviz ids
+	pub fn to_graphviz(&self) -> String {
+		return match self {
+			NodeId::Number(number) => number.to_string(),
+			NodeId::Label(label) => format!("\"{}\"", label.replace('\\', "\\\\").replace('"', "\\\"")),
+		};
 	}
 }
 
-impl std::cmp::PartialEq for GraphNode {
-	fn eq(&self, other: &Self) -> bool {
-		return self.attraction_number == other.attraction_number;
+impl std::fmt::Display for NodeId {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		return match self {
+			NodeId::Number(number) => write!(f, "{}", number),
+			NodeId::Label(label) => write!(f, "{}", label),
+		};
 	}
 }
 
+impl From<i64> for NodeId {
+	fn from(value: i64) -> Self {
+		return NodeId::Number(value);
+	}
+}
+
+// only ids that read back the same are numbers, so `007`, `-0` or `+5` keep how they were written
+impl From<&str> for NodeId {
+	fn from(value: &str) -> Self {
+		return match value.parse::<i64>() {
+			Ok(number) if number.to_string() == value => NodeId::Number(number),
+			_ => NodeId::Label(value.to_string()),
+		};
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct GraphNode {
+	pub id: NodeId,
+	pub x: u8, // data does not contain negative or larger than 255 coordinates
+	pub y: u8
+}
+
 impl GraphNode {
 	pub fn distance_to(&self, other: &GraphNode) -> f64 {
 		return (((self.x as i32 - other.x as i32).pow(2) + (self.y as i32 - other.y as i32).pow(2)) as f64).sqrt();
 	}
 
 	pub fn to_graphviz(&self) -> String {
-		return format!("{} [pos = \"{}, {}!\"]", self.attraction_number, self.x, self.y);
+		return format!("{} [pos = \"{}, {}!\"]", self.id.to_graphviz(), self.x, self.y);
 	}
 }
 
-enum AntError {
-	CannotMove,
+#[derive(Debug)]
+pub enum AntError {
+	CannotMove, // the ant has already visited every node
+	EmptyInstance,
+	DuplicateNode(NodeId),
+	TooManyNodes { node_count: usize, max_coordinate: u8 }, // more nodes than distinct positions to generate
+	InvalidChoiceInfo(NodeId), // NaN or infinite costs on the edges of this node, usually from extreme weights
+	Config(ConfigError),
+	InvalidSearchSetting { name: &'static str, value: usize, minimum: usize }, // see `SearchSettings::validate`
+	Parse { line: usize, message: String },
+	Io(std::io::Error),
+	Checkpoint(serde_json::Error),
 }
 
-#[derive(Debug, Clone)]
+impl std::fmt::Display for AntError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		return match self {
+			AntError::CannotMove => write!(f, "the ant has already visited every node"),
+			AntError::EmptyInstance => write!(f, "the instance has no nodes"),
+			AntError::DuplicateNode(id) => write!(f, "node {} appears more than once in the instance", id),
+			AntError::TooManyNodes { node_count, max_coordinate } => write!(f, "{} nodes don't fit on a grid with coordinates up to {}", node_count, max_coordinate),
+			AntError::InvalidChoiceInfo(id) => write!(f, "the edges of node {} have invalid costs, try smaller weights", id),
+			AntError::Config(error) => write!(f, "invalid configuration: {}", error),
+			AntError::InvalidSearchSetting { name, value, minimum } => write!(f, "the {} of the search has to be at least {}, got {}", name, minimum, value),
+			AntError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+			AntError::Io(error) => write!(f, "{}", error),
+			AntError::Checkpoint(error) => write!(f, "invalid checkpoint: {}", error),
+		};
+	}
+}
+
+impl std::error::Error for AntError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		return match self {
+			AntError::Config(error) => Some(error),
+			AntError::Io(error) => Some(error),
+			AntError::Checkpoint(error) => Some(error),
+			_ => None,
+		};
+	}
+}
+
+impl From<ConfigError> for AntError {
+	fn from(value: ConfigError) -> Self {
+		return AntError::Config(value);
+	}
+}
+
+impl From<std::io::Error> for AntError {
+	fn from(value: std::io::Error) -> Self {
+		return AntError::Io(value);
+	}
+}
+
+impl From<serde_json::Error> for AntError {
+	fn from(value: serde_json::Error) -> Self {
+		return AntError::Checkpoint(value);
+	}
+}
+
+// a line of an instance, the id is read as text so it isn't changed by parsing it as a number
+#[derive(Deserialize)]
+struct InstanceLine {
+	id: String,
+	x: u8,
+	y: u8,
+}
+
+// Reads an instance with one `id x y` line per node, separated by spaces. Ids can be any integer or a label without spaces
+pub fn read_instance<R: Read>(reader: R) -> Result<Vec<GraphNode>, AntError> {
+	let mut reader = csv::ReaderBuilder::new().has_headers(false).delimiter(b' ').trim(csv::Trim::All).from_reader(reader);
+	let mut nodes = Vec::new();
+	for result in reader.deserialize() {
+		let line: InstanceLine = result.map_err(|error| {
+			let line = error.position().map_or(0, |position| position.line() as usize);
+			return match error.into_kind() {
+				csv::ErrorKind::Io(error) => AntError::Io(error),
+				csv::ErrorKind::Deserialize { err, .. } => AntError::Parse { line, message: err.to_string() },
+				kind => AntError::Parse { line, message: format!("{:?}", kind) },
+			};
+		})?;
+		nodes.push(GraphNode { id: NodeId::from(line.id.as_str()), x: line.x, y: line.y });
+	}
+	return Ok(nodes);
+}
+
+pub fn read_instance_file(path: &Path) -> Result<Vec<GraphNode>, AntError> {
+	return read_instance(std::fs::File::open(path)?);
+}
+
+// Writes the format `read_instance` reads
+pub fn write_instance<W: Write>(nodes: &[GraphNode], writer: W) -> Result<(), AntError> {
+	let mut writer = csv::WriterBuilder::new().has_headers(false).delimiter(b' ').from_writer(writer);
+	for node in nodes {
+		writer.serialize(node).map_err(|error| match error.into_kind() {
+			csv::ErrorKind::Io(error) => AntError::Io(error),
+			kind => AntError::Io(std::io::Error::other(format!("{:?}", kind))),
+		})?;
+	}
+	writer.flush()?;
+	return Ok(());
+}
+
+// `node_count` nodes numbered from 1 with distinct coordinates between 0 and `max_coordinate`
+pub fn generate_instance(node_count: usize, max_coordinate: u8, seed: u64) -> Result<Vec<GraphNode>, AntError> {
+	let side = max_coordinate as usize + 1;
+	if node_count == 0 {
+		return Err(AntError::EmptyInstance);
+	}
+	if node_count > side * side {
+		return Err(AntError::TooManyNodes { node_count, max_coordinate });
+	}
+	let mut random_source = Xoshiro256PlusPlus::seed_from_u64(seed);
+	let positions = rand::seq::index::sample(&mut random_source, side * side, node_count);
+	return Ok(positions.into_iter().enumerate().map(|(index, position)| GraphNode {
+		id: NodeId::Number(index as i64 + 1),
+		x: (position % side) as u8,
+		y: (position / side) as u8,
+	}).collect());
+}
+
+// Nodes are referred to by their index in the instance, see `WorldState::node`
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Ant {
-	pub node_at: GraphNode,
-	pub current_path: ArrayVec<u8, { u8::MAX as usize }>, // Added assumption: attraction number is always one higher than index in the original 
+	pub node_at: usize,
+	pub current_path: Vec<usize>,
 	pub current_distance: f64,
 	pub random_choice_chance: f64, // less than 1
-	nodes_to_visit: Vec<GraphNode>,
-	cost_sums: ArrayVec<f64, { u8::MAX as usize }>,
+	nodes_to_visit: Vec<usize>,
+	#[serde(skip)]
+	cost_sums: Vec<f64>, // roulette scratch space, kept to avoid allocating on every step
 }
 
 impl Ant {
-	fn new(random_choice_chance: f64, nodes: Vec<GraphNode>) -> Self {
+	fn new(random_choice_chance: f64, node_count: usize) -> Self {
 		return Self {
-			node_at: GraphNode { attraction_number: 0, x: 0, y: 0 }, // empty init, randomize later
-			current_path: ArrayVec::new_const(),
+			node_at: 0, // randomized later
+			current_path: Vec::new(),
 			current_distance: 0.0,
 			random_choice_chance,
-			cost_sums: ArrayVec::new_const(),
-			nodes_to_visit: nodes,
+			cost_sums: Vec::new(),
+			nodes_to_visit: (0..node_count).collect(),
 		};
 	}
 	
-	fn move_ant(&mut self, world: &mut WorldState, random_source: &mut SmallRng) -> Result<(), AntError> {
+	fn move_ant(&mut self, world: &WorldState, random_source: &mut Xoshiro256PlusPlus) -> Result<(), AntError> {
 		// we're done
 		if self.nodes_to_visit.is_empty() {
 			return Err(AntError::CannotMove);
@@ -75,23 +247,30 @@ impl Ant {
 			} else {
 				let mut cost_sum = 0.0;
 				// create the costs table
-				for node in &mut self.nodes_to_visit {
-					let data = world.get_edge((self.node_at.attraction_number, node.attraction_number));
-					let cost = data.pheromone_cost * data.length_cost;
-					unsafe { self.cost_sums.push_unchecked(cost_sum); }
+				self.cost_sums.clear();
+				for node in &self.nodes_to_visit {
+					let cost = world.edges.get(self.node_at, *node).choice_info;
+					self.cost_sums.push(cost_sum);
 					cost_sum += cost;
 				}
+				if !cost_sum.is_finite() {
+					return Err(AntError::InvalidChoiceInfo(world.graph[self.node_at].id.clone()));
+				}
 
-				// roulette selection
-				let number_to_match = random_source.gen::<f64>() * cost_sum;
-				next_node_index = unsafe { self.cost_sums.binary_search_by(|v| v.partial_cmp(&number_to_match).unwrap_unchecked()).unwrap_err_unchecked() - 1 };
-				unsafe { self.cost_sums.set_len(0) }; // No need to drop anything - f64 doesn't impl Drop
+				if cost_sum > 0.0 {
+					// roulette selection, nodes with a cost of 0 share their running sum with the next node so they can't be picked
+					let number_to_match = random_source.gen::<f64>() * cost_sum;
+					next_node_index = self.cost_sums.partition_point(|value| *value <= number_to_match) - 1;
+				} else {
+					// nothing to tell the nodes apart, e.g. all of them share coordinates with this one
+					next_node_index = random_source.gen_range(0..self.nodes_to_visit.len());
+				}
 			}
 		}
 
-		self.current_path.push(self.node_at.attraction_number);
-		let next_node = unsafe { *self.nodes_to_visit.get_unchecked(next_node_index) };
-		self.current_distance += world.get_edge((self.node_at.attraction_number, next_node.attraction_number)).length;
+		self.current_path.push(self.node_at);
+		let next_node = self.nodes_to_visit[next_node_index];
+		self.current_distance += world.edges.get(self.node_at, next_node).length;
 		self.nodes_to_visit.swap_remove(next_node_index);
 		self.node_at = next_node;
 
@@ -104,225 +283,867 @@ impl Ant {
 	}
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EdgeData {
-	first_node: GraphNode,
-	second_node: GraphNode,
+	first_node: usize, // index in the instance
+	second_node: usize,
 	pheromone_strength: f64,
 	length: f64,
 	length_cost: f64, // 0 if length is 0
-	pheromone_cost: f64
+	choice_info: f64, // pheromone_strength ^ pheromone_weight * length_cost, refreshed once per iteration
 }
 
-#[derive(Debug, Clone)]
-pub struct ConfigData {
-	pub ant_count: usize,
-	pub random_choice_chance: f64,
-	pub pheromone_weight: f64,
-	pub heuristic_weight: f64,
-	pub iteration_count: u32,
-	pub pheromone_evaporation_coefficient: f64,
+// Lower triangle of the distance matrix, without the diagonal. Edge (i, j) with i > j lives at i * (i - 1) / 2 + j
+impl EdgeData {
+	pub fn first_node(&self) -> usize {
+		return self.first_node;
+	}
+
+	pub fn second_node(&self) -> usize {
+		return self.second_node;
+	}
+
+	pub fn pheromone_strength(&self) -> f64 {
+		return self.pheromone_strength;
+	}
+
+	pub fn length(&self) -> f64 {
+		return self.length;
+	}
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EdgeMatrix {
+	node_count: usize,
+	edges: Vec<EdgeData>,
+}
+
+impl EdgeMatrix {
+	fn new(nodes: &[GraphNode], heuristic_weight: f64, pheromone_weight: f64) -> Self {
+		let mut edges = Vec::with_capacity(nodes.len() * nodes.len().saturating_sub(1) / 2);
+		for (index, node) in nodes.iter().enumerate() {
+			for (second_index, second_node) in nodes[..index].iter().enumerate() {
+				let length = node.distance_to(second_node);
+				edges.push(EdgeData {
+					first_node: index,
+					second_node: second_index,
+					length,
+					pheromone_strength: 0.01,
+					length_cost: if length != 0.0 { length.recip().powf(heuristic_weight) } else { 0.0 },
+					choice_info: 0.0,
+				});
+			}
+		}
+		let mut result = Self {
+			node_count: nodes.len(),
+			edges,
+		};
+		result.update_choice_info(pheromone_weight);
+		return result;
+	}
+
+	fn update_choice_info(&mut self, pheromone_weight: f64) {
+		for edge in &mut self.edges {
+			let pheromone_cost = if edge.pheromone_strength == 0.0 { 1.0 } else { edge.pheromone_strength.powf(pheromone_weight) };
+			edge.choice_info = pheromone_cost * edge.length_cost;
+		}
+	}
+
+	fn index(first: usize, second: usize) -> usize {
+		debug_assert_ne!(first, second, "there is no edge from a node to itself");
+		let (row, column) = if first > second { (first, second) } else { (second, first) };
+		return row * (row - 1) / 2 + column;
+	}
+
+	pub fn node_count(&self) -> usize {
+		return self.node_count;
+	}
+
+	pub fn get(&self, first: usize, second: usize) -> &EdgeData {
+		return &self.edges[Self::index(first, second)];
+	}
+
+	pub fn get_mut(&mut self, first: usize, second: usize) -> &mut EdgeData {
+		return &mut self.edges[Self::index(first, second)];
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = &EdgeData> {
+		return self.edges.iter();
+	}
+
+	pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut EdgeData> {
+		return self.edges.iter_mut();
+	}
+
+	// average number of edges per node with pheromone of at least min + lambda * (max - min) of that node's edges
+	// close to 1 means the colony only follows one tour, close to node_count - 1 means it's still exploring
+	pub fn lambda_branching_factor(&self, lambda: f64) -> f64 {
+		if self.node_count < 2 {
+			return 0.0;
+		}
+		let mut branch_sum = 0;
+		for node in 0..self.node_count {
+			let pheromones = (0..self.node_count).filter(|other| *other != node).map(|other| self.get(node, other).pheromone_strength).collect_vec();
+			let (min, max) = pheromones.iter().fold((f64::MAX, f64::MIN), |(min, max), value| (min.min(*value), max.max(*value)));
+			let threshold = min + lambda * (max - min);
+			branch_sum += pheromones.iter().filter(|value| **value >= threshold).count();
+		}
+		return branch_sum as f64 / self.node_count as f64;
+	}
+
+	// Shannon entropy of the pheromone distribution over each node's edges, divided by its maximum and averaged over nodes
+	// 1 means pheromones are spread evenly, 0 means every node has a single edge holding all of it
+	pub fn pheromone_entropy(&self) -> f64 {
+		if self.node_count < 3 {
+			return 0.0;
+		}
+		let max_entropy = ((self.node_count - 1) as f64).ln();
+		let mut entropy_sum = 0.0;
+		for node in 0..self.node_count {
+			let total = (0..self.node_count).filter(|other| *other != node).map(|other| self.get(node, other).pheromone_strength).sum::<f64>();
+			if total <= 0.0 {
+				continue;
+			}
+			let entropy = (0..self.node_count).filter(|other| *other != node).map(|other| self.get(node, other).pheromone_strength / total).filter(|share| *share > 0.0).map(|share| -share * share.ln()).sum::<f64>();
+			entropy_sum += entropy / max_entropy;
+		}
+		return entropy_sum / self.node_count as f64;
+	}
+
+	pub fn pheromones_to_list(&self) -> SingleIterationEdgeList {
+		// create a graph with edges colored according to their pheromone strength
+		let mut result = SingleIterationEdgeList {
+			edges: HashMap::new(),
+			max_pheromones: f64::MIN,
+			min_pheromones: f64::MAX,
+		};
+
+		for edge in &self.edges {
+			result.edges.insert((edge.first_node, edge.second_node), edge.pheromone_strength);
+			if edge.pheromone_strength > result.max_pheromones {
+				result.max_pheromones = edge.pheromone_strength;
+			}
+			if edge.pheromone_strength < result.min_pheromones {
+				result.min_pheromones = edge.pheromone_strength;
+			}
+		}
+
+		return result;
+	}
 }
 
 #[derive(Debug, Clone)]
 pub struct SingleIterationEdgeList {
-	edges: HashMap<(GraphNode, GraphNode), f64>,
+	edges: HashMap<(usize, usize), f64>,
 	min_pheromones: f64,
 	max_pheromones: f64,
 }
 
 #[derive(Debug, Clone)]
 pub struct MultipleIterationGraphviz {
-	edge_lists: Vec<HashMap<(GraphNode, GraphNode), f64>>,
+	node_ids: Vec<NodeId>, // the edge lists only hold indices
+	edge_lists: Vec<HashMap<(usize, usize), f64>>,
 	min_pheromones: f64,
 	max_pheromones: f64,
 }
 
+// Wall-clock time spent in each phase of an iteration, or summed over the iterations of a run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PhaseTimings {
+	pub construction: Duration, // placing the ants and building their tours
+	pub best_update: Duration, // keeping track of the best tour
+	pub pheromone_update: Duration, // evaporation, deposits and the choice info
+}
+
+impl PhaseTimings {
+	pub fn total(&self) -> Duration {
+		return self.construction + self.best_update + self.pheromone_update;
+	}
+
+	fn add(&mut self, other: &PhaseTimings) {
+		self.construction += other.construction;
+		self.best_update += other.best_update;
+		self.pheromone_update += other.pheromone_update;
+	}
+}
+
+// What observers get to see after every iteration
+#[derive(Debug, Clone, Copy)]
+pub struct IterationInfo<'a> {
+	pub iteration: u32, // starts at 1
+	pub iteration_best_length: f64,
+	pub global_best_length: f64,
+	pub mean_length: f64, // over all ants of this iteration
+	pub elapsed: Duration,
+	pub timings: PhaseTimings, // of this iteration
+	pub ants: &'a [Ant],
+	pub pheromones: &'a EdgeMatrix,
+	pub nodes: &'a [GraphNode], // ant paths and edges hold indices into this
+}
+
+// Population diversity of a single iteration, see `IterationInfo::diversity_metrics`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct DiversityMetrics {
+	pub branching_factor: f64, // lambda-branching factor with lambda = 0.05
+	pub pheromone_entropy: f64,
+	pub mean_tour_distance: f64, // average number of edges in one ant's tour missing from another's, over all pairs of ants
+	pub best_tour_share: f64, // share of ants that built the iteration best tour
+}
+
+impl IterationInfo<'_> {
+	// takes O(ants² * nodes), so it is only computed when asked for
+	pub fn diversity_metrics(&self) -> DiversityMetrics {
+		let tours = self.ants.iter().map(|ant| {
+			return ant.current_path.windows(2).map(|pair| (pair[0].min(pair[1]), pair[0].max(pair[1]))).collect::<HashSet<_>>();
+		}).collect_vec();
+
+		let mut distance_sum = 0;
+		let mut pair_count = 0;
+		for (index, tour) in tours.iter().enumerate() {
+			for other_tour in &tours[index + 1 ..] {
+				distance_sum += tour.difference(other_tour).count();
+				pair_count += 1;
+			}
+		}
+
+		let best_tour = self.ants.iter().position(|ant| ant.current_distance == self.iteration_best_length).map(|index| &tours[index]);
+		let best_tour_count = tours.iter().filter(|tour| Some(*tour) == best_tour).count();
+
+		return DiversityMetrics {
+			branching_factor: self.pheromones.lambda_branching_factor(0.05),
+			pheromone_entropy: self.pheromones.pheromone_entropy(),
+			mean_tour_distance: if pair_count == 0 { 0.0 } else { distance_sum as f64 / pair_count as f64 },
+			best_tour_share: if tours.is_empty() { 0.0 } else { best_tour_count as f64 / tours.len() as f64 },
+		};
+	}
+}
+
+// Called by `WorldState::do_all_iterations_with_observer` after every iteration
+pub trait IterationObserver {
+	// `ControlFlow::Break` stops the run, it then ends with `TerminationReason::StoppedByObserver`
+	fn on_iteration(&mut self, info: &IterationInfo) -> ControlFlow<()>;
+}
+
+impl<O: IterationObserver + ?Sized> IterationObserver for &mut O {
+	fn on_iteration(&mut self, info: &IterationInfo) -> ControlFlow<()> {
+		return (**self).on_iteration(info);
+	}
+}
+
+impl<O: IterationObserver> IterationObserver for Option<O> {
+	fn on_iteration(&mut self, info: &IterationInfo) -> ControlFlow<()> {
+		return match self {
+			Some(observer) => observer.on_iteration(info),
+			None => ControlFlow::Continue(()),
+		};
+	}
+}
+
+// both observers see every iteration, the run stops if either of them asks for it
+impl<A: IterationObserver, B: IterationObserver> IterationObserver for (A, B) {
+	fn on_iteration(&mut self, info: &IterationInfo) -> ControlFlow<()> {
+		let first = self.0.on_iteration(info);
+		let second = self.1.on_iteration(info);
+		return if first.is_break() || second.is_break() { ControlFlow::Break(()) } else { ControlFlow::Continue(()) };
+	}
+}
+
+// Keeps the pheromone strength of every edge after every iteration
 #[derive(Debug, Clone)]
+pub struct EdgeRecorder {
+	pub recording: MultipleIterationGraphviz,
+}
+
+impl EdgeRecorder {
+	pub fn new() -> Self {
+		return Self {
+			recording: MultipleIterationGraphviz {
+				node_ids: Vec::new(),
+				edge_lists: Vec::new(),
+				max_pheromones: f64::MIN,
+				min_pheromones: f64::MAX,
+			},
+		};
+	}
+
+	// one graphviz edge list per iteration, colored from `low_color` to `high_color` by pheromone strength
+	pub fn to_graphviz_frames(self, low_color: colorgrad::Color, high_color: colorgrad::Color) -> Vec<String> {
+		let edge_recordings = self.recording;
+		if edge_recordings.edge_lists.is_empty() {
+			return Vec::new();
+		}
+		// the gradient needs a non-empty domain, with equal pheromones everywhere any color will do
+		let max_pheromones = if edge_recordings.max_pheromones > edge_recordings.min_pheromones { edge_recordings.max_pheromones } else { edge_recordings.min_pheromones + 1.0 };
+		let color_source = colorgrad::CustomGradient::new()
+			.colors(&[high_color, low_color])
+			.domain(&[edge_recordings.min_pheromones, max_pheromones])
+			.build().unwrap_or_else(|_| colorgrad::greys()); // only fails for NaN pheromones
+		let mut result = Vec::with_capacity(edge_recordings.edge_lists.len());
+
+		for iteration_edges in edge_recordings.edge_lists {
+			let mut iteration_edge_list = String::new();
+			for (pair, value) in iteration_edges {
+				let (first, second) = (&edge_recordings.node_ids[pair.0], &edge_recordings.node_ids[pair.1]);
+				iteration_edge_list.push_str(&format!("{} -- {} [color = \"{}\"]\n", first.to_graphviz(), second.to_graphviz(), color_source.at(value).to_hex_string()));
+			}
+			result.push(iteration_edge_list);
+		}
+
+		return result;
+	}
+}
+
+impl Default for EdgeRecorder {
+	fn default() -> Self {
+		return Self::new();
+	}
+}
+
+impl IterationObserver for EdgeRecorder {
+	fn on_iteration(&mut self, info: &IterationInfo) -> ControlFlow<()> {
+		if self.recording.node_ids.is_empty() {
+			self.recording.node_ids = info.nodes.iter().map(|node| node.id.clone()).collect();
+		}
+		let single_result = info.pheromones.pheromones_to_list();
+		self.recording.edge_lists.push(single_result.edges);
+		if single_result.max_pheromones > self.recording.max_pheromones {
+			self.recording.max_pheromones = single_result.max_pheromones;
+		}
+		if single_result.min_pheromones < self.recording.min_pheromones {
+			self.recording.min_pheromones = single_result.min_pheromones;
+		}
+		return ControlFlow::Continue(());
+	}
+}
+
+// One row of the convergence history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConvergenceRecord {
+	pub iteration: u32,
+	pub elapsed_seconds: f64,
+	pub iteration_seconds: f64,
+	pub construction_seconds: f64,
+	pub pheromone_update_seconds: f64,
+	pub iteration_best: f64,
+	pub global_best: f64,
+	pub mean_length: f64,
+	pub length_std_dev: f64, // population standard deviation of the ant tour lengths
+	pub pheromone_min: f64,
+	pub pheromone_max: f64,
+	pub branching_factor: f64,
+	pub pheromone_entropy: f64,
+	pub mean_tour_distance: f64,
+	pub best_tour_share: f64,
+}
+
+// Keeps a summary of every iteration, to see how fast the colony converges
+#[derive(Debug, Clone, Default)]
+pub struct ConvergenceRecorder {
+	pub history: Vec<ConvergenceRecord>,
+}
+
+impl ConvergenceRecorder {
+	pub fn new() -> Self {
+		return Self::default();
+	}
+
+	pub fn write_csv<W: Write>(&self, writer: W) -> std::io::Result<()> {
+		let mut writer = csv::Writer::from_writer(writer);
+		for record in &self.history {
+			writer.serialize(record)?;
+		}
+		writer.flush()?;
+		return Ok(());
+	}
+
+	pub fn write_json<W: Write>(&self, writer: W) -> std::io::Result<()> {
+		serde_json::to_writer_pretty(writer, &self.history)?;
+		return Ok(());
+	}
+}
+
+impl IterationObserver for ConvergenceRecorder {
+	fn on_iteration(&mut self, info: &IterationInfo) -> ControlFlow<()> {
+		let variance = info.ants.iter().map(|ant| (ant.current_distance - info.mean_length).powi(2)).sum::<f64>() / info.ants.len() as f64;
+		let (pheromone_min, pheromone_max) = info.pheromones.iter().fold((f64::MAX, f64::MIN), |(min, max), edge| {
+			return (min.min(edge.pheromone_strength), max.max(edge.pheromone_strength));
+		});
+		let diversity = info.diversity_metrics();
+		self.history.push(ConvergenceRecord {
+			iteration: info.iteration,
+			elapsed_seconds: info.elapsed.as_secs_f64(),
+			iteration_seconds: info.timings.total().as_secs_f64(),
+			construction_seconds: info.timings.construction.as_secs_f64(),
+			pheromone_update_seconds: info.timings.pheromone_update.as_secs_f64(),
+			iteration_best: info.iteration_best_length,
+			global_best: info.global_best_length,
+			mean_length: info.mean_length,
+			length_std_dev: variance.sqrt(),
+			pheromone_min,
+			pheromone_max,
+			branching_factor: diversity.branching_factor,
+			pheromone_entropy: diversity.pheromone_entropy,
+			mean_tour_distance: diversity.mean_tour_distance,
+			best_tour_share: diversity.best_tour_share,
+		});
+		return ControlFlow::Continue(());
+	}
+}
+
+// How a run went, in a form that can be serialized, see `WorldState::run_result`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConvergenceSummary {
+	pub iterations: u32,
+	pub best_iteration: u32, // iteration that found the tour
+	pub termination_reason: Option<TerminationReason>,
+	pub final_iteration_best: Option<f64>, // the last iteration's values, none if no iteration ran
+	pub final_mean_length: Option<f64>,
+	pub final_diversity: Option<DiversityMetrics>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunResult {
+	pub config: ConfigData,
+	pub seed: u64, // seed of the random source when the solver was created, the same seed gives the same first run
+	pub tour: Vec<GraphNode>,
+	pub length: f64,
+	pub runtime_seconds: f64,
+	pub construction_seconds: f64,
+	pub pheromone_update_seconds: f64,
+	pub convergence: ConvergenceSummary,
+}
+
+impl RunResult {
+	pub fn write_json<W: Write>(&self, writer: W) -> std::io::Result<()> {
+		serde_json::to_writer_pretty(writer, self)?;
+		return Ok(());
+	}
+}
+
+// Everything except the interrupt flag is saved in checkpoints, so a resumed run continues exactly where it stopped
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorldState {
-	graph: Vec<GraphNode>,
+	graph: Vec<GraphNode>, // internally nodes are only referred to by their index in here
 	pub ants: Vec<Ant>,
-	pub edges: Vec<EdgeData>,
-	iteration_count: u32,
+	pub edges: EdgeMatrix,
+	pub termination: TerminationCriteria,
 	pheromone_evaporation_coefficient: f64,
+	pub variant: AlgorithmVariant,
 	pub best_solution: Vec<GraphNode>,
+	best_path: Vec<usize>, // `best_solution` as indices
 	pub best_solution_length: f64,
+	pub current_iteration: u32, // number of finished iterations
+	pub best_solution_iteration: u32, // iteration that found `best_solution`, 0 if none yet
+	pub termination_reason: Option<TerminationReason>, // why the last run stopped
+	pub elapsed: Duration, // time spent iterating, carried over from checkpoints
+	#[serde(default)]
+	pub timings: PhaseTimings, // summed over the iterations of the current run, the rest of `elapsed` goes to termination checks and observers
+	#[serde(default)]
+	pub last_iteration_timings: PhaseTimings,
+	random_source: Xoshiro256PlusPlus,
+	seed: u64, // `random_source` was created from this
 	pub heuristic_weight: f64,
 	pub pheromone_weight: f64,
+	#[serde(skip)]
+	interrupt_flag: Option<Arc<AtomicBool>>, // shared with clones, so one flag can stop every thread
+}
+
+// Why a run ended before its termination criteria were met
+enum RunStop {
+	Failed(AntError),
+	ByObserver,
+}
+
+impl From<AntError> for RunStop {
+	fn from(value: AntError) -> Self {
+		return RunStop::Failed(value);
+	}
+}
+
+fn observer_result(flow: ControlFlow<()>) -> Result<(), RunStop> {
+	return match flow {
+		ControlFlow::Continue(()) => Ok(()),
+		ControlFlow::Break(()) => Err(RunStop::ByObserver),
+	};
+}
+
+fn deposit_on_path(edges: &mut EdgeMatrix, path: &[usize], amount: f64) {
+	if !amount.is_finite() {
+		return; // a tour of length 0, every node sits in the same spot and there is nothing to learn
+	}
+	for pair in path.windows(2) {
+		edges.get_mut(pair[0], pair[1]).pheromone_strength += amount;
+	}
 }
 
 impl WorldState {
-	pub fn new(input_nodes: Vec<GraphNode>, config: ConfigData) -> Self {
+	pub fn new(input_nodes: Vec<GraphNode>, config: ConfigData) -> Result<Self, AntError> {
+		config.validate()?;
+		if input_nodes.is_empty() {
+			return Err(AntError::EmptyInstance);
+		}
+		let mut seen_ids = HashSet::new();
+		for node in &input_nodes {
+			if !seen_ids.insert(&node.id) {
+				return Err(AntError::DuplicateNode(node.id.clone()));
+			}
+		}
+		let edges = EdgeMatrix::new(&input_nodes, config.heuristic_weight, config.pheromone_weight);
+		let seed = config.seed.unwrap_or_else(|| rand::thread_rng().gen()); // kept so unseeded runs can be repeated
 		let mut result = WorldState {
 			graph: input_nodes,
 			ants: Vec::with_capacity(config.ant_count),
-			edges: Vec::with_capacity(0x1 << 16),
-			iteration_count: config.iteration_count,
+			edges,
+			termination: config.termination,
 			pheromone_evaporation_coefficient: config.pheromone_evaporation_coefficient,
+			variant: config.variant,
 			best_solution: Vec::new(),
+			best_path: Vec::new(),
 			best_solution_length: f64::MAX,
+			current_iteration: 0,
+			best_solution_iteration: 0,
+			termination_reason: None,
+			elapsed: Duration::ZERO,
+			timings: PhaseTimings::default(),
+			last_iteration_timings: PhaseTimings::default(),
+			random_source: Xoshiro256PlusPlus::seed_from_u64(seed),
+			seed,
 			heuristic_weight: config.heuristic_weight,
 			pheromone_weight: config.pheromone_weight,
+			interrupt_flag: None,
 		};
-		unsafe {
-			result.edges.set_len(0x1 << 16);
-		}
 
 		for _ in 0..config.ant_count {
-			result.ants.push(Ant::new(config.random_choice_chance, result.graph.clone()));
+			result.ants.push(Ant::new(config.random_choice_chance, result.graph.len()));
 		}
 
-		result.init_edges();
+		return Ok(result);
+	}
 
-		return result;
+	// once `flag` is set, the run stops at the next iteration boundary keeping the best solution found so far
+	pub fn set_interrupt_flag(&mut self, flag: Arc<AtomicBool>) {
+		self.interrupt_flag = Some(flag);
+	}
+
+	pub fn is_interrupted(&self) -> bool {
+		return self.interrupt_flag.as_ref().is_some_and(|flag| flag.load(Ordering::Relaxed));
+	}
+
+	pub fn seed(&self) -> u64 {
+		return self.seed;
+	}
+
+	// restarts the random source, follow with `reset` for a fresh run
+	pub fn reseed(&mut self, seed: u64) {
+		self.seed = seed;
+		self.random_source = Xoshiro256PlusPlus::seed_from_u64(seed);
+	}
+
+	// the configuration this solver was created with, including the seed that was picked if none was set
+	pub fn config(&self) -> ConfigData {
+		return ConfigData {
+			ant_count: self.ants.len(),
+			random_choice_chance: self.ants.first().map_or(0.0, |ant| ant.random_choice_chance),
+			pheromone_weight: self.pheromone_weight,
+			heuristic_weight: self.heuristic_weight,
+			termination: self.termination.clone(),
+			pheromone_evaporation_coefficient: self.pheromone_evaporation_coefficient,
+			seed: Some(self.seed),
+			variant: self.variant,
+		};
+	}
+
+	// the best tour of the last run together with everything needed to repeat it
+	pub fn run_result(&self) -> RunResult {
+		let last_iteration = if self.current_iteration > 0 { Some(self.iteration_info()) } else { None };
+		return RunResult {
+			config: self.config(),
+			seed: self.seed,
+			tour: self.best_solution.clone(),
+			length: self.best_solution_length,
+			runtime_seconds: self.elapsed.as_secs_f64(),
+			construction_seconds: self.timings.construction.as_secs_f64(),
+			pheromone_update_seconds: self.timings.pheromone_update.as_secs_f64(),
+			convergence: ConvergenceSummary {
+				iterations: self.current_iteration,
+				best_iteration: self.best_solution_iteration,
+				termination_reason: self.termination_reason,
+				final_iteration_best: last_iteration.map(|info| info.iteration_best_length),
+				final_mean_length: last_iteration.map(|info| info.mean_length),
+				final_diversity: last_iteration.map(|info| info.diversity_metrics()),
+			},
+		};
 	}
 
 	fn init_edges(&mut self) {
-		for (index, node) in self.graph.iter().enumerate() {
-			for second_node in self.graph[index + 1 ..].iter() {
-				let length = node.distance_to(second_node);
-				let to_insert = EdgeData {
-					first_node: node.clone(),
-					second_node: second_node.clone(),
-					length,
-					pheromone_strength: 0.01,
-					length_cost: if length != 0.0 { length.recip().powf(self.heuristic_weight) } else { 0.0 },
-					pheromone_cost: (0.01_f64).powf(self.pheromone_weight),
-				};
-				let hash: u16;
-				let pair = (node.attraction_number, second_node.attraction_number);
-				if pair.0 > pair.1 {
-					hash = unsafe { std::mem::transmute(pair) };
-				} else {
-					let pair = (pair.1, pair.0);
-					hash = unsafe { std::mem::transmute(pair) };
-				}
-				self.edges[hash as usize] = to_insert;
-			}
-		}
+		self.edges = EdgeMatrix::new(&self.graph, self.heuristic_weight, self.pheromone_weight);
 	}
 	
 	pub fn init_ants(&mut self) {
 		for ant in &mut self.ants {
 			ant.clear();
-			ant.nodes_to_visit = self.graph.clone();
-			ant.node_at = self.graph.choose(&mut rand::thread_rng()).unwrap().clone();
-			ant.nodes_to_visit.swap_remove(ant.nodes_to_visit.iter().position(|x| *x == ant.node_at).unwrap());
+			ant.nodes_to_visit.clear();
+			ant.nodes_to_visit.extend(0..self.graph.len());
+			let start_index = self.random_source.gen_range(0..ant.nodes_to_visit.len());
+			ant.node_at = ant.nodes_to_visit.swap_remove(start_index);
 		}
 	}
 
-	fn get_edge(&mut self, pair: (u8, u8)) -> &mut EdgeData {
-		let hash: u16;
-		if pair.0 > pair.1 {
-			hash = unsafe { std::mem::transmute(pair) };
-		} else {
-			let pair = (pair.1, pair.0);
-			hash = unsafe { std::mem::transmute(pair) };
-		}
-		return unsafe { self.edges.get_unchecked_mut(hash as usize) };
+	pub fn nodes(&self) -> &[GraphNode] {
+		return &self.graph;
+	}
+
+	// the node behind an index in `Ant::current_path` or `EdgeData`
+	pub fn node(&self, index: usize) -> &GraphNode {
+		return &self.graph[index];
 	}
 
 	// moves ants until they're all done
-	fn move_ants(&mut self) {
-		let mut random_source = SmallRng::from_rng(rand::thread_rng()).unwrap();
-		let mut temp = self.ants.clone(); // evil clone to get around the borrow checker
-		for ant in &mut temp {
-			while ant.move_ant(self, &mut random_source).is_ok() {
-				//
+	fn move_ants(&mut self) -> Result<(), AntError> {
+		let mut random_source = self.random_source.clone(); // ants need to read the world while moving
+		let mut ants = std::mem::take(&mut self.ants);
+		let result = ants.iter_mut().try_for_each(|ant| {
+			loop {
+				match ant.move_ant(self, &mut random_source) {
+					Ok(()) => {},
+					Err(AntError::CannotMove) => break,
+					Err(error) => return Err(error),
+				}
 			}
-			ant.current_path.push(ant.node_at.attraction_number);
-			ant.current_distance += self.get_edge((ant.current_path[ant.current_path.len() - 2].clone(), ant.node_at.attraction_number)).length;
-		}
-		self.ants = temp;
+			// the last edge was already counted when the ant moved
+			ant.current_path.push(ant.node_at);
+			return Ok(());
+		});
+		self.ants = ants;
+		self.random_source = random_source;
+		return result;
 	}
 
 	fn update_pheromones(&mut self) {
 		// evaporate pheromones
-		for (index, node) in self.graph.clone().iter().enumerate() {
-			for second_node in self.graph.clone()[index + 1 ..].iter() {
-				self.get_edge((node.attraction_number, second_node.attraction_number)).pheromone_strength *= self.pheromone_evaporation_coefficient;
-			}
+		for edge in self.edges.iter_mut() {
+			edge.pheromone_strength *= self.pheromone_evaporation_coefficient;
 		}
 
 		// add pheromones
-		let pheromone_weight = self.pheromone_weight;
-		for ant in &self.ants.clone() {
-			for pair in ant.current_path.windows(2) {
-				let edge = self.get_edge((pair[0].clone(), pair[1].clone()));
-				edge.pheromone_strength += ant.current_distance.recip();
-				edge.pheromone_cost = if edge.pheromone_strength == 0.0 { 1.0 } else { edge.pheromone_strength.powf(pheromone_weight) };
-			}
+		match self.variant {
+			AlgorithmVariant::AntSystem => {
+				for ant in &self.ants {
+					deposit_on_path(&mut self.edges, &ant.current_path, ant.current_distance.recip());
+				}
+			},
+			AlgorithmVariant::ElitistAntSystem { elitist_weight } => {
+				for ant in &self.ants {
+					deposit_on_path(&mut self.edges, &ant.current_path, ant.current_distance.recip());
+				}
+				deposit_on_path(&mut self.edges, &self.best_path, elitist_weight / self.best_solution_length);
+			},
+			AlgorithmVariant::RankBasedAntSystem { ranked_ants } => {
+				let ranking = self.ants.iter().sorted_by(|a, b| a.current_distance.total_cmp(&b.current_distance)).take(ranked_ants - 1);
+				for (rank, ant) in ranking.enumerate() {
+					deposit_on_path(&mut self.edges, &ant.current_path, (ranked_ants - rank - 1) as f64 / ant.current_distance);
+				}
+				deposit_on_path(&mut self.edges, &self.best_path, ranked_ants as f64 / self.best_solution_length);
+			},
+			AlgorithmVariant::MaxMinAntSystem { min_max_ratio } => {
+				if let Some(ant) = self.ants.iter().min_by(|a, b| a.current_distance.total_cmp(&b.current_distance)) {
+					deposit_on_path(&mut self.edges, &ant.current_path, ant.current_distance.recip());
+				}
+				// nothing evaporates with a coefficient of 1, so there is no upper bound
+				if self.pheromone_evaporation_coefficient < 1.0 {
+					let max_pheromones = ((1.0 - self.pheromone_evaporation_coefficient) * self.best_solution_length).recip();
+					let min_pheromones = max_pheromones * min_max_ratio.unwrap_or((2.0 * self.graph.len() as f64).recip());
+					for edge in self.edges.iter_mut() {
+						edge.pheromone_strength = edge.pheromone_strength.clamp(min_pheromones, max_pheromones);
+					}
+				}
+			},
 		}
+
+		self.edges.update_choice_info(self.pheromone_weight);
 	}
 
 	fn update_best_solution(&mut self) {
 		for ant in &self.ants {
 			if ant.current_distance < self.best_solution_length {
-				self.best_solution = ant.current_path.iter().map(|x| {
-					return self.graph[(*x - 1) as usize].clone();
-				}).collect_vec();
+				self.best_solution = ant.current_path.iter().map(|index| self.graph[*index].clone()).collect_vec();
+				self.best_path.clone_from(&ant.current_path);
 				self.best_solution_length = ant.current_distance;
+				self.best_solution_iteration = self.current_iteration;
 			}
 		}
 	}
 
-	pub fn do_iteration(&mut self) {
+	pub fn do_iteration(&mut self) -> Result<(), AntError> {
+		self.current_iteration += 1;
+		let start = Instant::now();
 		self.init_ants();
-		self.move_ants();
+		self.move_ants()?;
+		let constructed = Instant::now();
+		self.update_best_solution(); // elitist variants deposit on the best tour including this iteration
+		let best_updated = Instant::now();
 		self.update_pheromones();
-		self.update_best_solution();
+		self.last_iteration_timings = PhaseTimings {
+			construction: constructed - start,
+			best_update: best_updated - constructed,
+			pheromone_update: best_updated.elapsed(),
+		};
+		let timings = self.last_iteration_timings;
+		self.timings.add(&timings);
+		return Ok(());
 	}
 
-	pub fn do_all_iterations(&mut self) {
-		for _ in 0..self.iteration_count {
-			self.do_iteration();
+	// runs iterations until the termination criteria are met, calling `after_iteration` after each one
+	// an error from `after_iteration` stops the run early and is passed on
+	fn run_until_termination<F: FnMut(&mut Self) -> Result<(), RunStop>>(&mut self, mut after_iteration: F) -> Result<TerminationReason, RunStop> {
+		let start = Instant::now();
+		let elapsed_before = self.elapsed;
+		let mut previously_met = Vec::new();
+		self.termination_reason = None;
+		loop {
+			let criteria = self.termination.check(self);
+			let reason = if self.is_interrupted() {
+				Some(TerminationReason::Interrupted)
+			} else if criteria.is_empty() {
+				Some(TerminationReason::MaxIterations)
+			} else {
+				match self.termination.mode {
+					TerminationMode::Any => criteria.iter().find(|(_, met)| *met).map(|(reason, _)| *reason),
+					TerminationMode::All if criteria.iter().all(|(_, met)| *met) => {
+						// report the criterion that was met last
+						let newly_met = criteria.iter().find(|(reason, _)| !previously_met.contains(reason));
+						Some(newly_met.unwrap_or(&criteria[0]).0)
+					},
+					TerminationMode::All => None,
+				}
+			};
+			if let Some(reason) = reason {
+				self.termination_reason = Some(reason);
+				return Ok(reason);
+			}
+			previously_met = criteria.into_iter().filter(|(_, met)| *met).map(|(reason, _)| reason).collect_vec();
+
+			self.do_iteration()?;
+			self.elapsed = elapsed_before + start.elapsed();
+			after_iteration(self)?;
 		}
 	}
 
-	pub fn do_all_iterations_with_edge_recording(&mut self) -> MultipleIterationGraphviz {
-		let mut result = MultipleIterationGraphviz {
-			edge_lists: Vec::with_capacity(self.iteration_count as usize),
-			max_pheromones: f64::MIN,
-			min_pheromones: f64::MAX,
+	// turns an early stop back into a termination reason, errors are passed on
+	fn finish_run(&mut self, result: Result<TerminationReason, RunStop>) -> Result<TerminationReason, AntError> {
+		return match result {
+			Ok(reason) => Ok(reason),
+			Err(RunStop::Failed(error)) => Err(error),
+			Err(RunStop::ByObserver) => {
+				self.termination_reason = Some(TerminationReason::StoppedByObserver);
+				Ok(TerminationReason::StoppedByObserver)
+			},
 		};
-		for _ in 0..self.iteration_count {
-			self.do_iteration();
-			let single_result = self.edge_pheromones_to_list();
-			result.edge_lists.push(single_result.edges);
-			if single_result.max_pheromones > result.max_pheromones {
-				result.max_pheromones = single_result.max_pheromones;
-			}
-			if single_result.min_pheromones < result.min_pheromones {
-				result.min_pheromones = single_result.min_pheromones;
+	}
+
+	pub fn do_all_iterations(&mut self) -> Result<TerminationReason, AntError> {
+		let result = self.run_until_termination(|_| Ok(()));
+		return self.finish_run(result);
+	}
+
+	// saves a checkpoint to `path` every `interval` iterations and once more if the run gets interrupted,
+	// an interval of 0 only saves on interruption
+	pub fn do_all_iterations_with_checkpoints<O: IterationObserver + ?Sized>(&mut self, interval: u32, path: &Path, observer: &mut O) -> Result<TerminationReason, AntError> {
+		let result = self.run_until_termination(|world| {
+			if interval != 0 && world.current_iteration % interval == 0 {
+				world.save_checkpoint(path)?;
 			}
+			return observer_result(observer.on_iteration(&world.iteration_info()));
+		});
+		let reason = self.finish_run(result)?;
+		if reason == TerminationReason::Interrupted {
+			self.save_checkpoint(path)?;
 		}
-		return result;
+		return Ok(reason);
 	}
 
-	pub fn do_all_iterations_with_graphviz_recording(&mut self, low_color: colorgrad::Color, high_color: colorgrad::Color) -> Vec<String> {
-		let edge_recordings = self.do_all_iterations_with_edge_recording();
-		let color_source = colorgrad::CustomGradient::new()
-			.colors(&[high_color, low_color])
-			.domain(&[edge_recordings.min_pheromones, edge_recordings.max_pheromones])
-			.build().unwrap();
-		let mut result = Vec::with_capacity(edge_recordings.edge_lists.len());
+	pub fn write_checkpoint<W: Write>(&self, writer: W) -> Result<(), AntError> {
+		serde_json::to_writer(writer, self)?;
+		return Ok(());
+	}
 
-		for iteration_edges in edge_recordings.edge_lists {
-			let mut iteration_edge_list = String::new();
-			for (pair, value) in iteration_edges {
-				iteration_edge_list.push_str(&format!("{} -- {} [color = \"{}\"]\n", pair.0.attraction_number, pair.1.attraction_number, color_source.at(value).to_hex_string()));
-			}
-			result.push(iteration_edge_list);
+	pub fn read_checkpoint<R: Read>(reader: R) -> Result<Self, AntError> {
+		let result: Self = serde_json::from_reader(reader)?;
+		let node_count = result.graph.len();
+		let valid_path = |path: &[usize]| path.iter().all(|index| *index < node_count);
+		if node_count == 0 || result.edges.node_count() != node_count || result.edges.edges.len() != node_count * (node_count - 1) / 2 || !valid_path(&result.best_path)
+			|| result.ants.iter().any(|ant| ant.node_at >= node_count || !valid_path(&ant.current_path) || !valid_path(&ant.nodes_to_visit)) {
+			return Err(AntError::Checkpoint(serde::de::Error::custom("the nodes don't match the edges")));
 		}
+		result.config().validate()?;
+		return Ok(result);
+	}
 
-		return result;
+	// writes to a temporary file first, so being killed mid-write never leaves a broken checkpoint behind
+	pub fn save_checkpoint(&self, path: &Path) -> Result<(), AntError> {
+		let temporary_path = path.with_extension("tmp");
+		let mut writer = std::io::BufWriter::new(std::fs::File::create(&temporary_path)?);
+		self.write_checkpoint(&mut writer)?;
+		writer.flush()?;
+		drop(writer);
+		std::fs::rename(temporary_path, path)?;
+		return Ok(());
+	}
+
+	pub fn load_checkpoint(path: &Path) -> Result<Self, AntError> {
+		return Self::read_checkpoint(std::io::BufReader::new(std::fs::File::open(path)?));
+	}
+
+	pub fn do_all_iterations_with_observer<O: IterationObserver + ?Sized>(&mut self, observer: &mut O) -> Result<TerminationReason, AntError> {
+		let result = self.run_until_termination(|world| observer_result(observer.on_iteration(&world.iteration_info())));
+		return self.finish_run(result);
+	}
+
+	// summary of the last iteration, as passed to observers
+	pub fn iteration_info(&self) -> IterationInfo<'_> {
+		let mut iteration_best_length = f64::MAX;
+		let mut length_sum = 0.0;
+		for ant in &self.ants {
+			iteration_best_length = iteration_best_length.min(ant.current_distance);
+			length_sum += ant.current_distance;
+		}
+		return IterationInfo {
+			iteration: self.current_iteration,
+			iteration_best_length,
+			global_best_length: self.best_solution_length,
+			mean_length: length_sum / self.ants.len() as f64,
+			elapsed: self.elapsed,
+			timings: self.last_iteration_timings,
+			ants: &self.ants,
+			pheromones: &self.edges,
+			nodes: &self.graph,
+		};
+	}
+
+	pub fn do_all_iterations_with_edge_recording(&mut self) -> Result<MultipleIterationGraphviz, AntError> {
+		let mut recorder = EdgeRecorder::new();
+		self.do_all_iterations_with_observer(&mut recorder)?;
+		return Ok(recorder.recording);
+	}
+
+	pub fn do_all_iterations_with_graphviz_recording(&mut self, low_color: colorgrad::Color, high_color: colorgrad::Color) -> Result<Vec<String>, AntError> {
+		let mut recorder = EdgeRecorder::new();
+		self.do_all_iterations_with_observer(&mut recorder)?;
+		return Ok(recorder.to_graphviz_frames(low_color, high_color));
 	}
 
 	pub fn reset(&mut self) {
 		self.init_ants();
 		self.init_edges();
 		self.best_solution = Vec::new();
+		self.best_path = Vec::new();
 		self.best_solution_length = f64::MAX;
+		self.current_iteration = 0;
+		self.best_solution_iteration = 0;
+		self.termination_reason = None;
+		self.elapsed = Duration::ZERO;
+		self.timings = PhaseTimings::default();
+		self.last_iteration_timings = PhaseTimings::default();
 	}
 
 	pub fn nodes_to_graphviz(&self) -> String {
@@ -337,7 +1158,7 @@ impl WorldState {
 	pub fn solution_edges_to_graphviz(&self) -> String {
 		let mut result = String::new();
 		for pair in self.best_solution.windows(2) {
-			result.push_str(&format!("{} -> {}\n", pair[0].attraction_number, pair[1].attraction_number));
+			result.push_str(&format!("{} -> {}\n", pair[0].id.to_graphviz(), pair[1].id.to_graphviz()));
 		}
 		return result;
 	}
@@ -353,38 +1174,92 @@ impl WorldState {
 		);
 	}
 
-	pub fn edge_pheromones_to_list(&self) -> SingleIterationEdgeList {
-		// create a graph with edges colored according to their pheromone strength
-		let mut result = SingleIterationEdgeList {
-			edges: HashMap::new(),
-			max_pheromones: f64::MIN,
-			min_pheromones: f64::MAX,
-		};
+	pub fn solution_to_json(&self) -> String {
+		return serde_json::to_string_pretty(&self.run_result()).expect("a run result always serializes");
+	}
 
-		for edge in &self.edges {
-			result.edges.insert((edge.first_node.clone(), edge.second_node.clone()), edge.pheromone_strength);
-			if edge.pheromone_strength > result.max_pheromones {
-				result.max_pheromones = edge.pheromone_strength;
-			}
-			if edge.pheromone_strength < result.min_pheromones {
-				result.min_pheromones = edge.pheromone_strength;
-			}
+	// one `position,id,x,y` row per node of the best tour
+	pub fn solution_to_csv(&self) -> String {
+		let mut result = String::from("position,id,x,y\n");
+		let mut writer = csv::WriterBuilder::new().has_headers(false).from_writer(Vec::new());
+		for (position, node) in self.best_solution.iter().enumerate() {
+			writer.serialize((position + 1, &node.id, node.x, node.y)).expect("writing to memory can't fail");
 		}
+		result.push_str(&String::from_utf8(writer.into_inner().expect("writing to memory can't fail")).expect("the rows are made of valid strings"));
+		return result;
+	}
 
+	// TSPLIB numbers nodes from 1, instances that don't do the same get their line numbers instead
+	pub fn solution_to_tsplib(&self, name: &str) -> String {
+		let uses_tsplib_ids = tour::uses_tsplib_ids(&self.graph);
+		let mut result = format!("NAME : {}\nTYPE : TOUR\n", name);
+		if uses_tsplib_ids {
+			result.push_str(&format!("COMMENT : Length {}\n", self.best_solution_length));
+		} else {
+			result.push_str(&format!("COMMENT : Length {}, nodes are numbered by their line in the instance\n", self.best_solution_length));
+		}
+		result.push_str(&format!("DIMENSION : {}\nTOUR_SECTION\n", self.best_solution.len()));
+		for (node, index) in self.best_solution.iter().zip(&self.best_path) {
+			match (&node.id, uses_tsplib_ids) {
+				(NodeId::Number(number), true) => result.push_str(&format!("{}\n", number)),
+				_ => result.push_str(&format!("{}\n", index + 1)),
+			}
+		}
+		result.push_str("-1\nEOF\n");
 		return result;
 	}
 
+	pub fn edge_pheromones_to_list(&self) -> SingleIterationEdgeList {
+		return self.edges.pheromones_to_list();
+	}
+
 	pub fn do_bruteforce(&mut self) {
 		// it's not supposed to be quick but it has to create a solution
-		for solution in self.graph.clone().into_iter().permutations(self.graph.len()).unique() {
+		for solution in (0..self.graph.len()).permutations(self.graph.len()) {
 			let mut sum = 0.0;
 			for pair in solution.windows(2) {
-				sum += self.get_edge((pair[0].attraction_number, pair[1].attraction_number)).length;
+				sum += self.edges.get(pair[0], pair[1]).length;
 			}
 			if sum < self.best_solution_length {
 				self.best_solution_length = sum;
-				self.best_solution = solution;
+				self.best_path = solution;
 			}
 		}
+		self.best_solution = self.best_path.iter().map(|index| self.graph[*index].clone()).collect_vec();
 	}
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn ids(instance: &str) -> Vec<NodeId> {
+		return read_instance(instance.as_bytes()).unwrap().into_iter().map(|node| node.id).collect();
+	}
+
+	#[test]
+	fn integer_ids_are_numbers() {
+		assert_eq!(ids("1 0 0\n-3 1 1\n42 2 2\n"), vec![NodeId::Number(1), NodeId::Number(-3), NodeId::Number(42)]);
+	}
+
+	#[test]
+	fn ids_that_dont_read_back_the_same_stay_labels() {
+		assert_eq!(ids("a 0 0\n007 3 4\nb 1 1\n"), vec![NodeId::Label("a".to_string()), NodeId::Label("007".to_string()), NodeId::Label("b".to_string())]);
+		assert_eq!(ids("1.5 0 0\n-0 1 1\n+5 2 2\n"), vec![NodeId::Label("1.5".to_string()), NodeId::Label("-0".to_string()), NodeId::Label("+5".to_string())]);
+		assert_eq!(NodeId::Label("007".to_string()).to_graphviz(), "\"007\"");
+	}
+
+	#[test]
+	fn written_instances_read_back_unchanged() {
+		let nodes = read_instance("007 3 4\n1.5 0 0\n-0 1 1\n12 2 2\n".as_bytes()).unwrap();
+		let mut written = Vec::new();
+		write_instance(&nodes, &mut written).unwrap();
+		assert_eq!(String::from_utf8(written.clone()).unwrap(), "007 3 4\n1.5 0 0\n-0 1 1\n12 2 2\n");
+		assert_eq!(read_instance(written.as_slice()).unwrap(), nodes);
+	}
+
+	#[test]
+	fn bad_coordinates_name_their_line() {
+		assert!(matches!(read_instance("a 0 0\nb 300 0\n".as_bytes()), Err(AntError::Parse { line: 2, .. })));
+	}
+}
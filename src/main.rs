@@ -1,208 +1,1633 @@
 #![allow(clippy::needless_return)]
-#![feature(generic_arg_infer)]
 
-use std::{path::PathBuf, collections::HashMap};
+use std::{path::{Path, PathBuf}, collections::HashMap, process::ExitCode};
+use std::sync::{Arc, LazyLock, atomic::{AtomicBool, Ordering}};
 
-use ant_colony::GraphNode;
-use clap::Parser;
+use ant_colony::{AntError, GraphNode};
+use clap::{ArgGroup, Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
+// raised by the first Ctrl-C or SIGTERM, every solver stops at its next iteration boundary
+static INTERRUPTED: LazyLock<Arc<AtomicBool>> = LazyLock::new(|| Arc::new(AtomicBool::new(false)));
 
-#[derive(Parser, Clone)]
-struct Config {
-	#[arg(short, long)]
+// brute force checks every permutation, past this it won't finish in reasonable time
+const MAX_EXACT_NODES: usize = 12;
+
+#[derive(ValueEnum, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum TerminationModeArg {
+	Any,
+	All,
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum VariantArg {
+	AntSystem,
+	Elitist,
+	RankBased,
+	MaxMin,
+}
+
+#[derive(ValueEnum, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum HistoryFormat {
+	Csv,
+	Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum OutputFormat {
+	#[default]
+	Graphviz,
+	Json, // the run result, tour ids and coordinates with the length, configuration and seed
+	Csv, // position, id and coordinates of every node in the tour
+	Tsplib, // a TSPLIB .tour file
+}
+
+impl OutputFormat {
+	fn extension(self) -> &'static str {
+		return match self {
+			OutputFormat::Graphviz => "dot",
+			OutputFormat::Json => "json",
+			OutputFormat::Csv => "csv",
+			OutputFormat::Tsplib => "tour",
+		};
+	}
+
+	// `name` ends up in the TSPLIB header
+	fn format_solution(self, solver: &ant_colony::WorldState, name: &str) -> String {
+		return match self {
+			OutputFormat::Graphviz => solver.solution_to_graphviz(),
+			OutputFormat::Json => solver.solution_to_json(),
+			OutputFormat::Csv => solver.solution_to_csv(),
+			OutputFormat::Tsplib => solver.solution_to_tsplib(name),
+		};
+	}
+}
+
+#[derive(ValueEnum, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum TuneMethod {
+	Race, // iterated F-race
+	Random, // random search
+	Halving, // successive halving
+	Hyperband,
+}
+
+#[derive(ValueEnum, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum ResourceArg {
+	Iterations,
+	Runs,
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum TourFormatArg {
+	Tsplib,
+	Csv,
+	Json,
+	List,
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum ObjectiveArg {
+	Open,
+	Closed,
+}
+
+#[derive(Parser)]
+#[command(about = "Ant colony optimisation for the shortest path through every node")]
+struct Cli {
+	#[command(subcommand)]
+	command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+	#[command(about = "Find a short tour and print it as a graphviz graph")]
+	Solve(SolveArgs),
+	#[command(about = "Repeat the run and print statistics on the found lengths")]
+	Stats(StatsArgs),
+	#[command(about = "Write a graphviz frame of the pheromones after every iteration")]
+	Record(RecordArgs),
+	#[command(about = "Run every combination of parameter values on every instance and write one row per run")]
+	Sweep(SweepArgs),
+	#[command(about = "Search the parameter ranges for the best configuration with iterated racing")]
+	Tune(TuneArgs),
+	#[command(about = "Find the shortest tour of a small instance by brute force")]
+	Exact(ExactArgs),
+	#[command(about = "Write a random instance")]
+	Generate(GenerateArgs),
+	#[command(about = "Check that a tour visits every node once and compute its length")]
+	Evaluate(EvaluateArgs),
+}
+
+// Options shared by everything that runs the colony
+#[derive(Args, Clone)]
+#[command(group(ArgGroup::new("termination").multiple(true).args(["iterations", "time-limit", "target-length", "stagnation-limit"])))]
+struct SolverArgs {
+	#[arg(long)]
+	config: Option<PathBuf>, // TOML or JSON file with any of the other options, picked by the extension
+	#[arg(long, name = "dump-config")]
+	dump_config: Option<PathBuf>, // write the effective options, including the seed, to a file that --config accepts
+	#[arg(short, long, overrides_with = "no-batch")]
 	batch: bool, // for processing directories
+	#[arg(long, name = "no-batch", overrides_with = "batch")]
+	no_batch: bool, // turns off `batch` from a config file
 	#[arg(short, long)]
-	path: PathBuf,
+	path: Option<PathBuf>,
 	#[arg(long, name = "ant-count")]
-	ant_count: usize,
+	ant_count: Option<usize>,
 	#[arg(long)]
-	iterations: u32,
+	iterations: Option<u32>,
+	#[arg(long, name = "time-limit")]
+	time_limit: Option<f64>, // seconds
+	#[arg(long, name = "target-length")]
+	target_length: Option<f64>,
+	#[arg(long, name = "stagnation-limit")]
+	stagnation_limit: Option<u32>, // iterations without improvement
+	#[arg(long, name = "termination-mode", value_enum)]
+	termination_mode: Option<TerminationModeArg>, // whether any or all of the set criteria have to be met, any if not set
 	#[arg(long, name = "evaporation")]
-	evaporation_coeff: f64,
+	evaporation_coeff: Option<f64>,
 	#[arg(long, name = "random-chance")]
-	random_choice_chance: f64,
+	random_choice_chance: Option<f64>,
 	#[arg(long, name = "pheromone-weight")]
-	pheromone_weight: f64,
+	pheromone_weight: Option<f64>,
 	#[arg(long, name = "heuristic-weight")]
-	heuristic_weight: f64,
-	#[arg(long, name = "try-count", conflicts_with = "record")]
-	try_count: Option<u32>,
-	#[arg(short, long, conflicts_with = "try-count")]
-	record: bool,
+	heuristic_weight: Option<f64>,
+	#[arg(long)]
+	seed: Option<u64>,
+	#[arg(long, value_enum)]
+	variant: Option<VariantArg>, // pheromone update rule, the variant specific parameters use the preset values if not set. Ant system if not set
+	#[arg(long, name = "elitist-weight")]
+	elitist_weight: Option<f64>, // only for the elitist variant, the ant count if not set
+	#[arg(long, name = "ranked-ants")]
+	ranked_ants: Option<usize>, // only for the rank-based variant, 6 if not set
+	#[arg(long, name = "min-max-ratio")]
+	min_max_ratio: Option<f64>, // only for the max-min variant, 1 / (2 * node count) if not set
+}
+
+#[derive(Args, Clone)]
+struct SolveArgs {
+	#[command(flatten)]
+	solver: SolverArgs,
+	#[arg(long, conflicts_with = "batch")]
+	checkpoint: Option<PathBuf>, // file to save the colony state to
+	#[arg(long, name = "checkpoint-every", requires = "checkpoint")]
+	checkpoint_every: Option<u32>, // iterations between checkpoints, 100 if not set
+	#[arg(long, conflicts_with_all = ["config", "dump-config", "batch", "path", "ant-count", "termination", "termination-mode", "evaporation", "random-chance", "pheromone-weight", "heuristic-weight", "seed", "variant", "elitist-weight", "ranked-ants", "min-max-ratio"])]
+	resume: Option<PathBuf>, // checkpoint to continue from, the instance and parameters are taken from it
+	#[arg(long, value_enum)]
+	history: Option<HistoryFormat>, // write a per-iteration convergence history next to the solution
+	#[arg(long, conflicts_with = "batch")]
+	result: Option<PathBuf>, // JSON file for the run result
+	#[arg(long, name = "output-format", value_enum)]
+	output_format: Option<OutputFormat>, // how the solution is printed, graphviz if not set
+}
+
+#[derive(Args, Clone)]
+struct StatsArgs {
+	#[command(flatten)]
+	solver: SolverArgs,
+	#[arg(long, name = "try-count")]
+	try_count: Option<u32>, // number of runs
+	#[arg(long, name = "success-target")]
+	success_target: Option<f64>, // runs at most this long count as successful, --target-length if not set
+	#[arg(long)]
+	threads: Option<usize>, // the number of CPUs if not set, doesn't change the results
+	#[arg(long, conflicts_with = "batch")]
+	result: Option<PathBuf>, // JSON file for the statistics
+	#[arg(long)]
+	records: Option<PathBuf>, // CSV file with one row per run, for every instance in batch mode
+}
+
+#[derive(Args, Clone)]
+struct RecordArgs {
+	#[command(flatten)]
+	solver: SolverArgs,
+	#[arg(long, value_enum)]
+	history: Option<HistoryFormat>, // write a per-iteration convergence history next to the frames
+	#[arg(long, name = "output-format", value_enum)]
+	output_format: Option<OutputFormat>, // how the solution is printed, graphviz if not set
+}
+
+// Every parameter takes a grid: comma separated values and `start:end:step` ranges that include the end, like `10,30,50` or `0:1:0.25`
+#[derive(Args)]
+struct SweepArgs {
+	#[arg(long)]
+	config: Option<PathBuf>, // TOML or JSON file with any of the other options, grids as strings or arrays
+	#[arg(short, long)]
+	path: Option<PathBuf>, // an instance or a directory of them
+	#[arg(long, name = "ant-count")]
+	ant_count: Option<String>,
+	#[arg(long)]
+	iterations: Option<String>,
+	#[arg(long, name = "time-limit")]
+	time_limit: Option<String>, // seconds
+	#[arg(long, name = "target-length")]
+	target_length: Option<String>,
+	#[arg(long, name = "stagnation-limit")]
+	stagnation_limit: Option<String>,
+	#[arg(long, name = "termination-mode", value_enum)]
+	termination_mode: Option<TerminationModeArg>, // the same for every set
+	#[arg(long, name = "evaporation-coeff")]
+	evaporation_coeff: Option<String>,
+	#[arg(long, name = "random-choice-chance")]
+	random_choice_chance: Option<String>,
+	#[arg(long, name = "pheromone-weight")]
+	pheromone_weight: Option<String>,
+	#[arg(long, name = "heuristic-weight")]
+	heuristic_weight: Option<String>,
+	#[arg(long)]
+	variant: Option<String>, // comma separated variant names, ant system if not set
+	#[arg(long)]
+	seed: Option<u64>, // base of the run seeds, shared by every set, random if not set
+	#[arg(long, name = "try-count")]
+	try_count: Option<u32>, // runs per set and instance
+	#[arg(long)]
+	threads: Option<usize>, // the number of CPUs if not set, doesn't change the results
+	#[arg(short, long)]
+	output: Option<PathBuf>, // CSV file for the results, stdout if not set
+}
+
+// Tuned parameters take a `min:max` range, a single value keeps them fixed
+#[derive(Args)]
+struct TuneArgs {
+	#[arg(long)]
+	config: Option<PathBuf>, // TOML or JSON file with any of the other options, ranges as strings or [min, max] arrays
+	#[arg(short, long)]
+	path: Option<PathBuf>, // a training instance or a directory of them
+	#[arg(long, name = "ant-count")]
+	ant_count: Option<String>,
+	#[arg(long, name = "evaporation-coeff")]
+	evaporation_coeff: Option<String>,
+	#[arg(long, name = "random-choice-chance")]
+	random_choice_chance: Option<String>,
+	#[arg(long, name = "pheromone-weight")]
+	pheromone_weight: Option<String>,
+	#[arg(long, name = "heuristic-weight")]
+	heuristic_weight: Option<String>,
+	#[arg(long)]
+	variant: Option<String>, // comma separated variant names to choose from, ant system if not set
+	#[arg(long)]
+	iterations: Option<u32>,
+	#[arg(long, name = "time-limit")]
+	time_limit: Option<f64>, // seconds
+	#[arg(long, name = "target-length")]
+	target_length: Option<f64>,
+	#[arg(long, name = "stagnation-limit")]
+	stagnation_limit: Option<u32>,
+	#[arg(long, name = "termination-mode", value_enum)]
+	termination_mode: Option<TerminationModeArg>,
+	#[arg(long, value_enum)]
+	method: Option<TuneMethod>, // race if not set
+	#[arg(long)]
+	budget: Option<usize>, // total number of runs of a race, 1000 if not set
+	#[arg(long, name = "first-test")]
+	first_test: Option<usize>, // instances every configuration runs on in a race before any is dropped, 5 if not set
+	#[arg(long)]
+	configurations: Option<usize>, // sampled by random search (50 if not set) and successive halving (27 if not set)
+	#[arg(long, value_enum)]
+	resource: Option<ResourceArg>, // what the budget of random search, successive halving and hyperband counts, runs if not set
+	#[arg(long, name = "min-resource")]
+	min_resource: Option<usize>, // smallest budget of a configuration, max resource / 27 (at least 1) if not set
+	#[arg(long, name = "max-resource")]
+	max_resource: Option<usize>, // largest budget of a configuration, --iterations or 9 runs if not set
+	#[arg(long, name = "reduction-factor")]
+	reduction_factor: Option<usize>, // successive halving keeps the best 1 / this of the configurations, 3 if not set
+	#[arg(long)]
+	repeats: Option<usize>, // runs per instance when the budget counts iterations, 1 if not set
+	#[arg(long)]
+	seed: Option<u64>, // random if not set
+	#[arg(long)]
+	threads: Option<usize>, // the number of CPUs if not set
+	#[arg(short, long)]
+	output: Option<PathBuf>, // config file for the best configuration, printed as TOML if not set
+}
+
+#[derive(Args)]
+struct ExactArgs {
+	#[arg(short, long)]
+	path: PathBuf,
+	#[arg(long, name = "output-format", value_enum, default_value = "graphviz")]
+	output_format: OutputFormat,
+}
+
+#[derive(Args)]
+struct GenerateArgs {
 	#[arg(long)]
-	bruteforce: bool, // if it's set ignore everything (other params still required) and spit out the optimal solution found using a brute force algo
+	nodes: usize,
+	#[arg(long, name = "max-coordinate", default_value_t = 100)]
+	max_coordinate: u8,
+	#[arg(long)]
+	seed: Option<u64>,
+	#[arg(short, long)]
+	output: Option<PathBuf>, // stdout if not set
+}
+
+#[derive(Args)]
+struct EvaluateArgs {
+	#[arg(short, long)]
+	path: PathBuf, // the instance
+	#[arg(long)]
+	tour: PathBuf, // node ids in visiting order
+	#[arg(long, name = "tour-format", value_enum)]
+	tour_format: Option<TourFormatArg>, // picked by the extension if not set: .tour, .csv, .json, anything else is a list of ids
+	#[arg(long, value_enum, default_value = "open")]
+	objective: ObjectiveArg, // open paths are what the colony optimises
+	#[arg(long)]
+	json: bool, // print the evaluation as JSON
+}
+
+// Config files use the long flag names as keys, values given on the command line take precedence.
+// One file can hold the options of every subcommand, each one only reads the keys it has flags for
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct ConfigFile {
+	batch: bool,
+	path: Option<PathBuf>,
+	ant_count: Option<usize>,
+	iterations: Option<u32>,
+	time_limit: Option<f64>,
+	target_length: Option<f64>,
+	stagnation_limit: Option<u32>,
+	termination_mode: Option<TerminationModeArg>,
+	evaporation_coeff: Option<f64>,
+	random_choice_chance: Option<f64>,
+	pheromone_weight: Option<f64>,
+	heuristic_weight: Option<f64>,
+	seed: Option<u64>,
+	variant: Option<VariantArg>,
+	elitist_weight: Option<f64>,
+	ranked_ants: Option<usize>,
+	min_max_ratio: Option<f64>,
+	try_count: Option<u32>,
+	success_target: Option<f64>,
+	threads: Option<usize>,
+	records: Option<PathBuf>,
+	checkpoint: Option<PathBuf>,
+	checkpoint_every: Option<u32>,
+	history: Option<HistoryFormat>,
+	result: Option<PathBuf>,
+	output_format: Option<OutputFormat>,
 }
 
-impl From<&Config> for ant_colony::ConfigData {
-	fn from(value: &Config) -> Self {
-		return Self {
-			ant_count: value.ant_count,
-			heuristic_weight: value.heuristic_weight,
-			iteration_count: value.iterations,
-			pheromone_evaporation_coefficient: value.evaporation_coeff,
-			pheromone_weight: value.pheromone_weight,
-			random_choice_chance: value.random_choice_chance,
+// A grid in a sweep config file, numbers, strings with the command line syntax or arrays of either
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum GridSpec {
+	Number(f64),
+	Text(String),
+	List(Vec<GridItem>),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum GridItem {
+	Number(f64),
+	Text(String),
+}
+
+impl GridSpec {
+	// in the command line syntax
+	fn to_text(&self) -> String {
+		let item_text = |item: &GridItem| match item {
+			GridItem::Number(number) => number.to_string(),
+			GridItem::Text(text) => text.clone(),
+		};
+		return match self {
+			GridSpec::Number(number) => number.to_string(),
+			GridSpec::Text(text) => text.clone(),
+			GridSpec::List(items) => items.iter().map(item_text).collect::<Vec<_>>().join(","),
 		};
 	}
 }
 
-struct BatchRunData {
-	pub min_result: f64,
-	pub max_result: f64,
-	pub average: f64,
-	pub run_count: u32,
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct SweepFile {
+	path: Option<PathBuf>,
+	ant_count: Option<GridSpec>,
+	iterations: Option<GridSpec>,
+	time_limit: Option<GridSpec>,
+	target_length: Option<GridSpec>,
+	stagnation_limit: Option<GridSpec>,
+	termination_mode: Option<TerminationModeArg>,
+	evaporation_coeff: Option<GridSpec>,
+	random_choice_chance: Option<GridSpec>,
+	pheromone_weight: Option<GridSpec>,
+	heuristic_weight: Option<GridSpec>,
+	variant: Option<GridSpec>,
+	seed: Option<u64>,
+	try_count: Option<u32>,
+	threads: Option<usize>,
+	output: Option<PathBuf>,
+}
+
+impl SweepFile {
+	fn load(path: &Path) -> Result<SweepFile, String> {
+		let text = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+		return match is_toml(path) {
+			true => toml::from_str(&text).map_err(|error| error.to_string()),
+			false => serde_json::from_str(&text).map_err(|error| error.to_string()),
+		};
+	}
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct TuneFile {
+	path: Option<PathBuf>,
+	ant_count: Option<GridSpec>,
+	evaporation_coeff: Option<GridSpec>,
+	random_choice_chance: Option<GridSpec>,
+	pheromone_weight: Option<GridSpec>,
+	heuristic_weight: Option<GridSpec>,
+	variant: Option<GridSpec>,
+	iterations: Option<u32>,
+	time_limit: Option<f64>,
+	target_length: Option<f64>,
+	stagnation_limit: Option<u32>,
+	termination_mode: Option<TerminationModeArg>,
+	method: Option<TuneMethod>,
+	budget: Option<usize>,
+	first_test: Option<usize>,
+	configurations: Option<usize>,
+	resource: Option<ResourceArg>,
+	min_resource: Option<usize>,
+	max_resource: Option<usize>,
+	reduction_factor: Option<usize>,
+	repeats: Option<usize>,
+	seed: Option<u64>,
+	threads: Option<usize>,
+	output: Option<PathBuf>,
+}
+
+impl TuneFile {
+	fn load(path: &Path) -> Result<TuneFile, String> {
+		let text = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+		return match is_toml(path) {
+			true => toml::from_str(&text).map_err(|error| error.to_string()),
+			false => serde_json::from_str(&text).map_err(|error| error.to_string()),
+		};
+	}
+}
+
+impl ConfigFile {
+	fn load(path: &Path) -> Result<ConfigFile, String> {
+		let text = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+		return match is_toml(path) {
+			true => toml::from_str(&text).map_err(|error| error.to_string()),
+			false => serde_json::from_str(&text).map_err(|error| error.to_string()),
+		};
+	}
+
+	fn save(&self, path: &Path) -> Result<(), String> {
+		let text = match is_toml(path) {
+			true => toml::to_string_pretty(self).map_err(|error| error.to_string())?,
+			false => serde_json::to_string_pretty(self).map_err(|error| error.to_string())?,
+		};
+		return std::fs::write(path, text).map_err(|error| error.to_string());
+	}
+}
+
+fn is_toml(path: &Path) -> bool {
+	return path.extension().is_some_and(|extension| extension == "toml");
+}
+
+fn usage_error(kind: clap::error::ErrorKind, message: impl std::fmt::Display) -> clap::Error {
+	return Cli::command().error(kind, message);
 }
 
-impl BatchRunData {
-	fn new() -> Self {
-		return Self {
-			min_result: f64::MAX,
-			max_result: f64::MIN,
-			average: 0.0,
-			run_count: 0,
+impl SolverArgs {
+	// fills everything not given on the command line from `file`
+	fn merge(&mut self, file: &ConfigFile) {
+		self.batch = !self.no_batch && (self.batch || file.batch);
+		self.path = self.path.take().or(file.path.clone());
+		self.ant_count = self.ant_count.or(file.ant_count);
+		self.iterations = self.iterations.or(file.iterations);
+		self.time_limit = self.time_limit.or(file.time_limit);
+		self.target_length = self.target_length.or(file.target_length);
+		self.stagnation_limit = self.stagnation_limit.or(file.stagnation_limit);
+		self.termination_mode = self.termination_mode.or(file.termination_mode);
+		self.evaporation_coeff = self.evaporation_coeff.or(file.evaporation_coeff);
+		self.random_choice_chance = self.random_choice_chance.or(file.random_choice_chance);
+		self.pheromone_weight = self.pheromone_weight.or(file.pheromone_weight);
+		self.heuristic_weight = self.heuristic_weight.or(file.heuristic_weight);
+		self.seed = self.seed.or(file.seed);
+		self.variant = self.variant.or(file.variant);
+		self.elitist_weight = self.elitist_weight.or(file.elitist_weight);
+		self.ranked_ants = self.ranked_ants.or(file.ranked_ants);
+		self.min_max_ratio = self.min_max_ratio.or(file.min_max_ratio);
+	}
+
+	fn to_file(&self) -> ConfigFile {
+		return ConfigFile {
+			batch: self.batch,
+			path: self.path.clone(),
+			ant_count: self.ant_count,
+			iterations: self.iterations,
+			time_limit: self.time_limit,
+			target_length: self.target_length,
+			stagnation_limit: self.stagnation_limit,
+			termination_mode: self.termination_mode,
+			evaporation_coeff: self.evaporation_coeff,
+			random_choice_chance: self.random_choice_chance,
+			pheromone_weight: self.pheromone_weight,
+			heuristic_weight: self.heuristic_weight,
+			seed: self.seed,
+			variant: self.variant,
+			elitist_weight: self.elitist_weight,
+			ranked_ants: self.ranked_ants,
+			min_max_ratio: self.min_max_ratio,
+			..ConfigFile::default()
+		};
+	}
+
+	fn load_config(&self) -> Result<ConfigFile, String> {
+		return match &self.config {
+			Some(path) => ConfigFile::load(path).map_err(|error| format!("Could not read the config file {}: {}", path.display(), error)),
+			None => Ok(ConfigFile::default()),
 		};
 	}
 
-	fn add_run(&mut self, result: f64) {
-		if result > self.max_result {
-			self.max_result = result;
+	// clap only sees the command line, so whatever depends on the config file is checked here
+	fn check(&self) -> Result<(), clap::Error> {
+		let missing = [
+			("--path", self.path.is_none()),
+			("--ant-count", self.ant_count.is_none()),
+			("--evaporation-coeff", self.evaporation_coeff.is_none()),
+			("--random-choice-chance", self.random_choice_chance.is_none()),
+			("--pheromone-weight", self.pheromone_weight.is_none()),
+			("--heuristic-weight", self.heuristic_weight.is_none()),
+		].into_iter().filter(|(_, missing)| *missing).map(|(name, _)| name).collect::<Vec<_>>();
+		if !missing.is_empty() {
+			return Err(usage_error(clap::error::ErrorKind::MissingRequiredArgument, format!("the following options have to be set on the command line or in the config file: {}", missing.join(", "))));
 		}
-		if result < self.min_result {
-			self.min_result = result;
+		if self.iterations.is_none() && self.time_limit.is_none() && self.target_length.is_none() && self.stagnation_limit.is_none() {
+			return Err(usage_error(clap::error::ErrorKind::MissingRequiredArgument, "at least one of --iterations, --time-limit, --target-length or --stagnation-limit is required"));
 		}
-		let previous_sum = self.average * self.run_count as f64;
-		self.run_count += 1;
-		self.average = (previous_sum + result) / self.run_count as f64;
+		let variant = self.variant.unwrap_or(VariantArg::AntSystem);
+		let misplaced = [
+			("--elitist-weight", self.elitist_weight.is_some(), VariantArg::Elitist),
+			("--ranked-ants", self.ranked_ants.is_some(), VariantArg::RankBased),
+			("--min-max-ratio", self.min_max_ratio.is_some(), VariantArg::MaxMin),
+		].into_iter().find(|(_, set, owner)| *set && *owner != variant);
+		if let Some((name, _, owner)) = misplaced {
+			return Err(usage_error(clap::error::ErrorKind::ArgumentConflict, format!("{} only applies to --variant {}", name, owner.to_possible_value().unwrap().get_name())));
+		}
+		if let Err(error) = ant_colony::ConfigData::try_from(self) {
+			return Err(usage_error(clap::error::ErrorKind::ValueValidation, format!("invalid configuration: {}", error)));
+		}
+		return Ok(());
 	}
 
-	fn add_batch(&mut self, other: Self) {
-		if other.max_result > self.max_result {
-			self.max_result = other.max_result;
+	// a random seed is picked here so the dump repeats the run exactly, TOML integers only go up to i64::MAX
+	fn dump(&mut self, extra: ConfigFile) -> Result<(), String> {
+		let Some(path) = self.dump_config.clone() else {
+			return Ok(());
+		};
+		self.seed.get_or_insert_with(|| rand::thread_rng().gen_range(0..=i64::MAX as u64));
+		self.termination_mode.get_or_insert(TerminationModeArg::Any);
+		self.variant.get_or_insert(VariantArg::AntSystem);
+		// the preset values are written out so a later change of the presets doesn't change the run,
+		// only the default min-max ratio is left out since it depends on the instance
+		if let Ok(config) = ant_colony::ConfigData::try_from(&*self) {
+			match config.variant {
+				ant_colony::AlgorithmVariant::AntSystem => {},
+				ant_colony::AlgorithmVariant::ElitistAntSystem { elitist_weight } => self.elitist_weight = Some(elitist_weight),
+				ant_colony::AlgorithmVariant::RankBasedAntSystem { ranked_ants } => self.ranked_ants = Some(ranked_ants),
+				ant_colony::AlgorithmVariant::MaxMinAntSystem { min_max_ratio } => self.min_max_ratio = min_max_ratio,
+			}
 		}
-		if other.min_result < self.min_result {
-			self.min_result = other.min_result;
+		// `extra` holds the options of the subcommand
+		let file = ConfigFile {
+			try_count: extra.try_count,
+			success_target: extra.success_target,
+			threads: extra.threads,
+			records: extra.records,
+			checkpoint: extra.checkpoint,
+			checkpoint_every: extra.checkpoint_every,
+			history: extra.history,
+			result: extra.result,
+			output_format: extra.output_format,
+			..self.to_file()
+		};
+		return file.save(&path).map_err(|error| format!("Could not write the config to {}: {}", path.display(), error));
+	}
+}
+
+impl SolveArgs {
+	fn merge(&mut self, file: &ConfigFile) {
+		self.solver.merge(file);
+		self.checkpoint = self.checkpoint.take().or(file.checkpoint.clone());
+		self.checkpoint_every = self.checkpoint_every.or(file.checkpoint_every);
+		self.history = self.history.or(file.history);
+		self.result = self.result.take().or(file.result.clone());
+		self.output_format = self.output_format.or(file.output_format);
+	}
+
+	fn check(&self) -> Result<(), clap::Error> {
+		self.solver.check()?;
+		let conflicts = [
+			("--checkpoint", self.checkpoint.is_some()),
+			("--result", self.result.is_some()),
+		];
+		if let Some((name, _)) = conflicts.into_iter().find(|(_, set)| *set && self.solver.batch) {
+			return Err(usage_error(clap::error::ErrorKind::ArgumentConflict, format!("{} can't be used with --batch", name)));
 		}
-		let self_sum = self.average * self.run_count as f64;
-		let other_sum = other.average * other.run_count as f64;
-		self.run_count += other.run_count;
-		self.average = (self_sum + other_sum) / self.run_count as f64;
+		if self.checkpoint_every.is_some() && self.checkpoint.is_none() {
+			return Err(usage_error(clap::error::ErrorKind::MissingRequiredArgument, "--checkpoint-every requires --checkpoint"));
+		}
+		if self.checkpoint_every == Some(0) {
+			return Err(usage_error(clap::error::ErrorKind::InvalidValue, "--checkpoint-every has to be at least 1"));
+		}
+		return Ok(());
+	}
+
+	fn to_file(&self) -> ConfigFile {
+		return ConfigFile {
+			checkpoint: self.checkpoint.clone(),
+			checkpoint_every: self.checkpoint_every,
+			history: self.history,
+			result: self.result.clone(),
+			output_format: self.output_format,
+			..ConfigFile::default()
+		};
 	}
 }
 
-// first trim the leading spaces from files with `cut -c 2-`
+impl StatsArgs {
+	fn merge(&mut self, file: &ConfigFile) {
+		self.solver.merge(file);
+		self.try_count = self.try_count.or(file.try_count);
+		self.success_target = self.success_target.or(file.success_target);
+		self.threads = self.threads.or(file.threads);
+		self.result = self.result.take().or(file.result.clone());
+		self.records = self.records.take().or(file.records.clone());
+	}
 
-// returns string that was printed before
-fn process_set_of_nodes(nodes: Vec::<ant_colony::GraphNode>, config: Config, dir_to_write: &PathBuf) -> String {
-	let world_config = ant_colony::ConfigData::from(&config);
-	let mut solver = ant_colony::WorldState::new(nodes, world_config);
-	if let Some(tries) = config.try_count {
-		let tries_per_thread = (tries as usize).div_ceil(num_cpus::get());
-		let mut threads = Vec::with_capacity(num_cpus::get());
-		for _ in 0..num_cpus::get() {
-			let mut thread_solver = solver.clone();
-			threads.push(std::thread::spawn(move || {
-				let mut run_stats = BatchRunData::new();
-				for _ in 0..tries_per_thread {
-					thread_solver.do_all_iterations();
-					run_stats.add_run(thread_solver.best_solution_length);
-					thread_solver.reset();
+	fn check(&self) -> Result<(), clap::Error> {
+		self.solver.check()?;
+		if self.try_count.is_none() {
+			return Err(usage_error(clap::error::ErrorKind::MissingRequiredArgument, "--try-count has to be set on the command line or in the config file"));
+		}
+		if self.try_count == Some(0) {
+			return Err(usage_error(clap::error::ErrorKind::ValueValidation, "--try-count has to be at least 1"));
+		}
+		if self.threads == Some(0) {
+			return Err(usage_error(clap::error::ErrorKind::ValueValidation, "--threads has to be at least 1"));
+		}
+		if self.result.is_some() && self.solver.batch {
+			return Err(usage_error(clap::error::ErrorKind::ArgumentConflict, "--result can't be used with --batch"));
+		}
+		return Ok(());
+	}
+
+	fn to_file(&self) -> ConfigFile {
+		return ConfigFile {
+			try_count: self.try_count,
+			success_target: self.success_target,
+			threads: self.threads,
+			result: self.result.clone(),
+			records: self.records.clone(),
+			..ConfigFile::default()
+		};
+	}
+}
+
+impl RecordArgs {
+	fn merge(&mut self, file: &ConfigFile) {
+		self.solver.merge(file);
+		self.history = self.history.or(file.history);
+		self.output_format = self.output_format.or(file.output_format);
+	}
+
+	fn to_file(&self) -> ConfigFile {
+		return ConfigFile {
+			history: self.history,
+			output_format: self.output_format,
+			..ConfigFile::default()
+		};
+	}
+}
+
+impl SweepArgs {
+	fn merge(&mut self, file: SweepFile) {
+		let grid = |spec: Option<GridSpec>| spec.map(|spec| spec.to_text());
+		self.path = self.path.take().or(file.path);
+		self.ant_count = self.ant_count.take().or(grid(file.ant_count));
+		self.iterations = self.iterations.take().or(grid(file.iterations));
+		self.time_limit = self.time_limit.take().or(grid(file.time_limit));
+		self.target_length = self.target_length.take().or(grid(file.target_length));
+		self.stagnation_limit = self.stagnation_limit.take().or(grid(file.stagnation_limit));
+		self.termination_mode = self.termination_mode.or(file.termination_mode);
+		self.evaporation_coeff = self.evaporation_coeff.take().or(grid(file.evaporation_coeff));
+		self.random_choice_chance = self.random_choice_chance.take().or(grid(file.random_choice_chance));
+		self.pheromone_weight = self.pheromone_weight.take().or(grid(file.pheromone_weight));
+		self.heuristic_weight = self.heuristic_weight.take().or(grid(file.heuristic_weight));
+		self.variant = self.variant.take().or(grid(file.variant));
+		self.seed = self.seed.or(file.seed);
+		self.try_count = self.try_count.or(file.try_count);
+		self.threads = self.threads.or(file.threads);
+		self.output = self.output.take().or(file.output);
+	}
+
+	fn check(&self) -> Result<(), clap::Error> {
+		let missing = [
+			("--path", self.path.is_none()),
+			("--ant-count", self.ant_count.is_none()),
+			("--evaporation-coeff", self.evaporation_coeff.is_none()),
+			("--random-choice-chance", self.random_choice_chance.is_none()),
+			("--pheromone-weight", self.pheromone_weight.is_none()),
+			("--heuristic-weight", self.heuristic_weight.is_none()),
+			("--try-count", self.try_count.is_none()),
+		].into_iter().filter(|(_, missing)| *missing).map(|(name, _)| name).collect::<Vec<_>>();
+		if !missing.is_empty() {
+			return Err(usage_error(clap::error::ErrorKind::MissingRequiredArgument, format!("the following options have to be set on the command line or in the config file: {}", missing.join(", "))));
+		}
+		if self.try_count == Some(0) {
+			return Err(usage_error(clap::error::ErrorKind::ValueValidation, "--try-count has to be at least 1"));
+		}
+		if self.iterations.is_none() && self.time_limit.is_none() && self.target_length.is_none() && self.stagnation_limit.is_none() {
+			return Err(usage_error(clap::error::ErrorKind::MissingRequiredArgument, "at least one of --iterations, --time-limit, --target-length or --stagnation-limit is required"));
+		}
+		if self.threads == Some(0) {
+			return Err(usage_error(clap::error::ErrorKind::ValueValidation, "--threads has to be at least 1"));
+		}
+		return Ok(());
+	}
+
+	// every combination of the grids, in the order of the flags with the last one changing fastest
+	fn parameter_sets(&self) -> Result<Vec<ant_colony::ConfigData>, clap::Error> {
+		let numbers = |name: &str, spec: &Option<String>| -> Result<Vec<Option<f64>>, clap::Error> {
+			return match spec {
+				Some(spec) => Ok(parse_grid(name, spec)?.into_iter().map(Some).collect()),
+				None => Ok(vec![None]),
+			};
+		};
+		let integers = |name: &str, spec: &Option<String>, max: u32| -> Result<Vec<Option<u32>>, clap::Error> {
+			return numbers(name, spec)?.into_iter().map(|value| match value {
+				Some(value) if value.fract() != 0.0 || value < 0.0 || value > max as f64 => Err(usage_error(clap::error::ErrorKind::ValueValidation, format!("{} has to be a whole number from 0 to {}, got {}", name, max, value))),
+				value => Ok(value.map(|value| value as u32)),
+			}).collect();
+		};
+		let variants = match &self.variant {
+			Some(spec) => spec.split(',').map(|name| VariantArg::from_str(name.trim(), true).map(Some)
+				.map_err(|_| usage_error(clap::error::ErrorKind::InvalidValue, format!("--variant: unknown variant {}", name.trim())))).collect::<Result<Vec<_>, _>>()?,
+			None => vec![None],
+		};
+		let grid = itertools::iproduct!(
+			variants,
+			integers("--ant-count", &self.ant_count, u32::MAX)?,
+			integers("--iterations", &self.iterations, u32::MAX)?,
+			numbers("--time-limit", &self.time_limit)?,
+			numbers("--target-length", &self.target_length)?,
+			integers("--stagnation-limit", &self.stagnation_limit, u32::MAX)?,
+			numbers("--evaporation-coeff", &self.evaporation_coeff)?,
+			numbers("--random-choice-chance", &self.random_choice_chance)?,
+			numbers("--pheromone-weight", &self.pheromone_weight)?,
+			numbers("--heuristic-weight", &self.heuristic_weight)?
+		);
+		let mut sets = Vec::new();
+		for (variant, ant_count, iterations, time_limit, target_length, stagnation_limit, evaporation_coeff, random_choice_chance, pheromone_weight, heuristic_weight) in grid {
+			let args = SolverArgs {
+				config: None,
+				dump_config: None,
+				batch: false,
+				no_batch: false,
+				path: None,
+				ant_count: ant_count.map(|count| count as usize),
+				iterations,
+				time_limit,
+				target_length,
+				stagnation_limit,
+				termination_mode: self.termination_mode,
+				evaporation_coeff,
+				random_choice_chance,
+				pheromone_weight,
+				heuristic_weight,
+				seed: self.seed,
+				variant,
+				elitist_weight: None,
+				ranked_ants: None,
+				min_max_ratio: None,
+			};
+			let config = ant_colony::ConfigData::try_from(&args)
+				.map_err(|error| usage_error(clap::error::ErrorKind::ValueValidation, format!("invalid parameter set {}: {}", sets.len(), error)))?;
+			sets.push(config);
+		}
+		return Ok(sets);
+	}
+}
+
+// A numeric option of `tune`, either fixed or a range to search
+#[derive(Clone, Copy)]
+enum TuneRange {
+	Fixed(f64),
+	Range(f64, f64),
+}
+
+impl TuneArgs {
+	fn merge(&mut self, file: TuneFile) {
+		// a [min, max] array is a range, anything else uses the command line syntax
+		let range = |spec: Option<GridSpec>| spec.map(|spec| match &spec {
+			GridSpec::List(items) if items.len() == 2 => match (&items[0], &items[1]) {
+				(GridItem::Number(min), GridItem::Number(max)) => format!("{}:{}", min, max),
+				_ => spec.to_text(),
+			},
+			_ => spec.to_text(),
+		});
+		self.path = self.path.take().or(file.path);
+		self.ant_count = self.ant_count.take().or(range(file.ant_count));
+		self.evaporation_coeff = self.evaporation_coeff.take().or(range(file.evaporation_coeff));
+		self.random_choice_chance = self.random_choice_chance.take().or(range(file.random_choice_chance));
+		self.pheromone_weight = self.pheromone_weight.take().or(range(file.pheromone_weight));
+		self.heuristic_weight = self.heuristic_weight.take().or(range(file.heuristic_weight));
+		self.variant = self.variant.take().or(file.variant.map(|spec| spec.to_text()));
+		self.iterations = self.iterations.or(file.iterations);
+		self.time_limit = self.time_limit.or(file.time_limit);
+		self.target_length = self.target_length.or(file.target_length);
+		self.stagnation_limit = self.stagnation_limit.or(file.stagnation_limit);
+		self.termination_mode = self.termination_mode.or(file.termination_mode);
+		self.method = self.method.or(file.method);
+		self.budget = self.budget.or(file.budget);
+		self.first_test = self.first_test.or(file.first_test);
+		self.configurations = self.configurations.or(file.configurations);
+		self.resource = self.resource.or(file.resource);
+		self.min_resource = self.min_resource.or(file.min_resource);
+		self.max_resource = self.max_resource.or(file.max_resource);
+		self.reduction_factor = self.reduction_factor.or(file.reduction_factor);
+		self.repeats = self.repeats.or(file.repeats);
+		self.seed = self.seed.or(file.seed);
+		self.threads = self.threads.or(file.threads);
+		self.output = self.output.take().or(file.output);
+	}
+
+	fn check(&self) -> Result<(), clap::Error> {
+		let missing = [
+			("--path", self.path.is_none()),
+			("--ant-count", self.ant_count.is_none()),
+			("--evaporation-coeff", self.evaporation_coeff.is_none()),
+			("--random-choice-chance", self.random_choice_chance.is_none()),
+			("--pheromone-weight", self.pheromone_weight.is_none()),
+			("--heuristic-weight", self.heuristic_weight.is_none()),
+		].into_iter().filter(|(_, missing)| *missing).map(|(name, _)| name).collect::<Vec<_>>();
+		if !missing.is_empty() {
+			return Err(usage_error(clap::error::ErrorKind::MissingRequiredArgument, format!("the following options have to be set on the command line or in the config file: {}", missing.join(", "))));
+		}
+		if self.iterations.is_none() && self.time_limit.is_none() && self.target_length.is_none() && self.stagnation_limit.is_none() {
+			return Err(usage_error(clap::error::ErrorKind::MissingRequiredArgument, "at least one of --iterations, --time-limit, --target-length or --stagnation-limit is required"));
+		}
+		if self.threads == Some(0) {
+			return Err(usage_error(clap::error::ErrorKind::ValueValidation, "--threads has to be at least 1"));
+		}
+		if self.reduction_factor.is_some_and(|factor| factor < 2) {
+			return Err(usage_error(clap::error::ErrorKind::ValueValidation, "--reduction-factor has to be at least 2"));
+		}
+		if let ResourceArg::Iterations = self.resource.unwrap_or(ResourceArg::Runs) {
+			if self.max_resource.or(self.iterations.map(|iterations| iterations as usize)).is_none() {
+				return Err(usage_error(clap::error::ErrorKind::MissingRequiredArgument, "--resource iterations needs --max-resource or --iterations"));
+			}
+		}
+		let settings = self.search_settings();
+		if settings.max_resource == 0 || settings.min_resource > settings.max_resource || settings.repeats == 0 {
+			return Err(usage_error(clap::error::ErrorKind::ValueValidation, "the resources need 1 <= --min-resource <= --max-resource and --repeats has to be at least 1"));
+		}
+		return Ok(());
+	}
+
+	// for everything but racing
+	fn search_settings(&self) -> ant_colony::SearchSettings {
+		let resource = self.resource.unwrap_or(ResourceArg::Runs);
+		let max_resource = self.max_resource.unwrap_or(match resource {
+			ResourceArg::Iterations => self.iterations.unwrap_or(0) as usize,
+			ResourceArg::Runs => 9,
+		});
+		return ant_colony::SearchSettings {
+			resource: match resource {
+				ResourceArg::Iterations => ant_colony::BudgetResource::Iterations,
+				ResourceArg::Runs => ant_colony::BudgetResource::Runs,
+			},
+			min_resource: self.min_resource.unwrap_or((max_resource / 27).max(1)),
+			max_resource,
+			reduction_factor: self.reduction_factor.unwrap_or(3),
+			repeats: self.repeats.unwrap_or(1),
+			seed: self.seed.unwrap_or_default(),
+			thread_count: self.threads.unwrap_or_else(num_cpus::get),
+			interrupt_flag: Some(INTERRUPTED.clone()),
+		};
+	}
+
+	// the tuned parameters and the options every run shares, with the fixed values already filled in
+	fn parameters(&self) -> Result<(Vec<ant_colony::TuningParameter>, SolverArgs), clap::Error> {
+		let mut base = SolverArgs {
+			config: None,
+			dump_config: None,
+			batch: false,
+			no_batch: false,
+			path: None,
+			ant_count: None,
+			iterations: self.iterations,
+			time_limit: self.time_limit,
+			target_length: self.target_length,
+			stagnation_limit: self.stagnation_limit,
+			termination_mode: self.termination_mode,
+			evaporation_coeff: None,
+			random_choice_chance: None,
+			pheromone_weight: None,
+			heuristic_weight: None,
+			seed: None,
+			variant: None,
+			elitist_weight: None,
+			ranked_ants: None,
+			min_max_ratio: None,
+		};
+		let mut parameters = Vec::new();
+		let numeric = [
+			("ant-count", &self.ant_count),
+			("evaporation-coeff", &self.evaporation_coeff),
+			("random-choice-chance", &self.random_choice_chance),
+			("pheromone-weight", &self.pheromone_weight),
+			("heuristic-weight", &self.heuristic_weight),
+		];
+		let (mut lowest, mut highest) = (base.clone(), base.clone());
+		for (name, spec) in numeric {
+			let range = parse_tune_range(name, spec.as_deref().unwrap())?;
+			let (min, max) = match range {
+				TuneRange::Fixed(value) => (value, value),
+				TuneRange::Range(min, max) => (min, max),
+			};
+			if name == "ant-count" && (min.fract() != 0.0 || min < 1.0 || max.fract() != 0.0) {
+				return Err(usage_error(clap::error::ErrorKind::ValueValidation, "--ant-count has to be a whole number of at least 1"));
+			}
+			set_tuned_value(&mut lowest, name, &ant_colony::ParameterValue::Real(min));
+			set_tuned_value(&mut highest, name, &ant_colony::ParameterValue::Real(max));
+			match range {
+				TuneRange::Fixed(value) => set_tuned_value(&mut base, name, &ant_colony::ParameterValue::Real(value)),
+				TuneRange::Range(min, max) => parameters.push(ant_colony::TuningParameter {
+					name: name.to_string(),
+					domain: if name == "ant-count" { ant_colony::ParameterDomain::Integer { min: min as i64, max: max as i64 } } else { ant_colony::ParameterDomain::Real { min, max } },
+				}),
+			}
+		}
+		let variants = match &self.variant {
+			Some(spec) => spec.split(',').map(|name| VariantArg::from_str(name.trim(), true)
+				.map_err(|_| usage_error(clap::error::ErrorKind::InvalidValue, format!("--variant: unknown variant {}", name.trim())))).collect::<Result<Vec<_>, _>>()?,
+			None => vec![VariantArg::AntSystem],
+		};
+		// checking both ends of every range with every variant catches values the algorithm can't run with
+		for (args, end) in [(&mut lowest, "lower"), (&mut highest, "upper")] {
+			for variant in &variants {
+				args.variant = Some(*variant);
+				if let Err(error) = ant_colony::ConfigData::try_from(&*args) {
+					return Err(usage_error(clap::error::ErrorKind::ValueValidation, format!("invalid configuration at the {} end of the ranges: {}", end, error)));
 				}
-				return run_stats;
-			}));
+			}
+		}
+		match variants[..] {
+			[variant] => base.variant = Some(variant),
+			_ => parameters.push(ant_colony::TuningParameter {
+				name: "variant".to_string(),
+				domain: ant_colony::ParameterDomain::Categorical(variants.iter().map(|variant| variant.to_possible_value().unwrap().get_name().to_string()).collect()),
+			}),
 		}
+		if parameters.is_empty() {
+			return Err(usage_error(clap::error::ErrorKind::MissingRequiredArgument, "at least one parameter needs a min:max range or a choice of variants to tune"));
+		}
+		return Ok((parameters, base));
+	}
+}
 
-		let result = threads.into_iter().map(|handle| {
-			return handle.join();
-		}).reduce(|a, b| {
-			let mut batch = a.unwrap();
-			batch.add_batch(b.unwrap());
-			return Ok(batch);
-		}).unwrap().unwrap();
-		return format!("Finished {} runs. Longest found route is {}, shortest found route is {}. The average length is {}", result.run_count, result.max_result, result.min_result, result.average);
-	} else {
-		if config.record {
-			let low_color = colorgrad::Color::from_linear_rgba(0.0, 0.0, 1.0, 1.0);
-			let high_color = colorgrad::Color::from_linear_rgba(1.0, 0.0, 0.0, 0.0);
-			let frames = solver.do_all_iterations_with_graphviz_recording(low_color, high_color);
-			let nodes = solver.nodes_to_graphviz();
-			for (index, item) in frames.iter().enumerate() {
-				let output = format!("graph frame{} {{\n\
-					layout = \"neato\"\n\
-					labelloc = \"t\"\n\
-					overlap = \"prism\"\n\
-					label = \"Frame {} of {}. Found solution is {}.\\nPositions not to scale.\"\n\
-					{}\n\n\
-					{}\n\
-					}}
-					", index, index, frames.len(), solver.best_solution_length, nodes, item
-				);
-				std::fs::write(format!("./{}/{}.dot", dir_to_write.display(), index), output).unwrap();
+fn parse_tune_range(name: &str, spec: &str) -> Result<TuneRange, clap::Error> {
+	let invalid = |message: String| usage_error(clap::error::ErrorKind::ValueValidation, format!("--{}: {}", name, message));
+	let number = |text: &str| text.trim().parse::<f64>().map_err(|_| invalid(format!("{} is not a number", text.trim())));
+	return match spec.split(':').collect::<Vec<_>>()[..] {
+		[value] => Ok(TuneRange::Fixed(number(value)?)),
+		[min, max] => {
+			let (min, max) = (number(min)?, number(max)?);
+			if !(min.is_finite() && max.is_finite() && min < max) {
+				return Err(invalid(format!("{} is not a range, it needs min < max", spec)));
 			}
-		} else {
-			if config.bruteforce {
-				//solver.do_bruteforce()
-			} else {
-				solver.do_all_iterations();
+			Ok(TuneRange::Range(min, max))
+		},
+		_ => Err(invalid(format!("{} is neither a value nor a min:max range", spec))),
+	};
+}
+
+// `name` is one of the parameters `TuneArgs::parameters` creates
+fn set_tuned_value(args: &mut SolverArgs, name: &str, value: &ant_colony::ParameterValue) {
+	let number = match value {
+		ant_colony::ParameterValue::Integer(value) => *value as f64,
+		ant_colony::ParameterValue::Real(value) => *value,
+		ant_colony::ParameterValue::Category(category) => {
+			args.variant = VariantArg::from_str(category, true).ok();
+			return;
+		},
+	};
+	match name {
+		"ant-count" => args.ant_count = Some(number as usize),
+		"evaporation-coeff" => args.evaporation_coeff = Some(number),
+		"random-choice-chance" => args.random_choice_chance = Some(number),
+		"pheromone-weight" => args.pheromone_weight = Some(number),
+		"heuristic-weight" => args.heuristic_weight = Some(number),
+		_ => unreachable!("unknown tuned parameter {}", name),
+	}
+}
+
+// `start:end:step` ranges include the end, their values are rounded so steps like 0.1 don't pile up float noise
+fn parse_grid(name: &str, spec: &str) -> Result<Vec<f64>, clap::Error> {
+	let invalid = |message: String| usage_error(clap::error::ErrorKind::ValueValidation, format!("{}: {}", name, message));
+	let number = |text: &str| text.trim().parse::<f64>().map_err(|_| invalid(format!("{} is not a number", text.trim())));
+	let mut values = Vec::new();
+	for item in spec.split(',') {
+		let parts = item.split(':').collect::<Vec<_>>();
+		match parts[..] {
+			[value] => values.push(number(value)?),
+			[start, end, step] => {
+				let (start, end, step) = (number(start)?, number(end)?, number(step)?);
+				if !(step > 0.0 && step.is_finite() && start.is_finite() && end >= start) {
+					return Err(invalid(format!("{} is not a range, it needs start <= end and a positive step", item.trim())));
+				}
+				let count = ((end - start) / step + 1e-9).floor() as usize + 1;
+				values.extend((0..count).map(|index| ((start + index as f64 * step) * 1e9).round() / 1e9));
+			},
+			_ => return Err(invalid(format!("{} is neither a value nor a start:end:step range", item.trim()))),
+		}
+	}
+	return Ok(values);
+}
+
+impl TryFrom<&SolverArgs> for ant_colony::ConfigData {
+	type Error = ant_colony::ConfigError;
+
+	fn try_from(value: &SolverArgs) -> Result<Self, Self::Error> {
+		let variant = value.variant.unwrap_or(VariantArg::AntSystem);
+		let time_limit = value.time_limit.map(|seconds| std::time::Duration::try_from_secs_f64(seconds).map_err(|_| ant_colony::ConfigError::InvalidTimeLimit(seconds))).transpose()?;
+		let mut builder = match variant {
+			VariantArg::AntSystem => ant_colony::ConfigBuilder::ant_system(),
+			VariantArg::Elitist => ant_colony::ConfigBuilder::elitist_ant_system(),
+			VariantArg::RankBased => ant_colony::ConfigBuilder::rank_based_ant_system(),
+			VariantArg::MaxMin => ant_colony::ConfigBuilder::max_min_ant_system(),
+		};
+		// a set variant parameter replaces the preset's, `check` makes sure it belongs to the variant
+		match variant {
+			VariantArg::AntSystem => {},
+			VariantArg::Elitist => if let Some(elitist_weight) = value.elitist_weight {
+				builder = builder.variant(ant_colony::AlgorithmVariant::ElitistAntSystem { elitist_weight });
+			},
+			VariantArg::RankBased => if let Some(ranked_ants) = value.ranked_ants {
+				builder = builder.variant(ant_colony::AlgorithmVariant::RankBasedAntSystem { ranked_ants });
+			},
+			VariantArg::MaxMin => if value.min_max_ratio.is_some() {
+				builder = builder.variant(ant_colony::AlgorithmVariant::MaxMinAntSystem { min_max_ratio: value.min_max_ratio });
+			},
+		}
+		return builder
+			.ant_count(value.ant_count.unwrap())
+			.heuristic_weight(value.heuristic_weight.unwrap())
+			.pheromone_weight(value.pheromone_weight.unwrap())
+			.pheromone_evaporation_coefficient(value.evaporation_coeff.unwrap())
+			.random_choice_chance(value.random_choice_chance.unwrap())
+			.max_iterations(value.iterations)
+			.time_limit(time_limit)
+			.target_length(value.target_length)
+			.stagnation_limit(value.stagnation_limit)
+			.termination_mode(match value.termination_mode.unwrap_or(TerminationModeArg::Any) {
+				TerminationModeArg::Any => ant_colony::TerminationMode::Any,
+				TerminationModeArg::All => ant_colony::TerminationMode::All,
+			})
+			.seed(value.seed)
+			.build();
+	}
+}
+
+// The subcommands that run the colony, with their options after merging the config file
+#[derive(Clone)]
+enum Task {
+	Solve(SolveArgs),
+	Stats(StatsArgs),
+	Record(RecordArgs),
+}
+
+impl Task {
+	fn solver_args(&self) -> &SolverArgs {
+		return match self {
+			Task::Solve(args) => &args.solver,
+			Task::Stats(args) => &args.solver,
+			Task::Record(args) => &args.solver,
+		};
+	}
+
+	// statistics don't have a solution to print
+	fn output_format(&self) -> OutputFormat {
+		return match self {
+			Task::Solve(args) => args.output_format.unwrap_or_default(),
+			Task::Stats(_) => OutputFormat::Graphviz,
+			Task::Record(args) => args.output_format.unwrap_or_default(),
+		};
+	}
+}
+
+// first trim the leading spaces from files with `cut -c 2-`
+
+// returns string that was printed before
+// `name` identifies the instance in the output
+fn process_set_of_nodes(nodes: Vec::<ant_colony::GraphNode>, task: &Task, name: &str, dir_to_write: &Path) -> Result<String, AntError> {
+	let world_config = ant_colony::ConfigData::try_from(task.solver_args())?;
+	let solver = ant_colony::WorldState::new(nodes, world_config)?;
+	return process_solver(solver, task, name, dir_to_write);
+}
+
+fn process_solver(mut solver: ant_colony::WorldState, task: &Task, name: &str, dir_to_write: &Path) -> Result<String, AntError> {
+	solver.set_interrupt_flag(INTERRUPTED.clone());
+	match task {
+		Task::Solve(args) => run_solve(&mut solver, args, dir_to_write)?,
+		Task::Stats(args) => {
+			let (output, records) = run_stats(solver, args, name)?;
+			if let Some(path) = &args.records {
+				write_result(path, |writer| ant_colony::write_records_csv(&records, writer));
 			}
+			return Ok(output);
+		},
+		Task::Record(args) => run_record(&mut solver, args, dir_to_write)?,
+	}
+	if let Some(reason) = solver.termination_reason {
+		eprintln!("Stopped after {} iterations: {}", solver.current_iteration, reason);
+	}
+	eprintln!("Found solution with length {}", solver.best_solution_length);
+	return Ok(task.output_format().format_solution(&solver, name));
+}
+
+// returns the printed statistics and a record of every run
+fn run_stats(solver: ant_colony::WorldState, args: &StatsArgs, name: &str) -> Result<(String, Vec<ant_colony::ExperimentRecord>), AntError> {
+	let result = ant_colony::run_batch(&solver, args.try_count.unwrap() as usize, args.threads.unwrap_or_else(num_cpus::get))?;
+	let config = solver.config();
+	let records = result.runs.iter().map(|run| ant_colony::ExperimentRecord::new(0, name, &config, run)).collect::<Vec<_>>();
+	let summary = result.summary(args.success_target.or(args.solver.target_length));
+	// stat_collector reads the first sentences, keep them as they are
+	let mut output = format!("Finished {} runs. Longest found route is {}, shortest found route is {}. The average length is {}", summary.run_count, summary.max, summary.min, summary.mean);
+	output.push_str(&format!(". Standard deviation {}, median {}, quartiles {} to {}, 95% confidence interval of the mean {} to {}, best tour found at iteration {} on average, {} seconds per run",
+		summary.std_dev, summary.median, summary.first_quartile, summary.third_quartile, summary.confidence_low, summary.confidence_high, summary.mean_best_iteration, summary.mean_runtime_seconds));
+	output.push_str(&format!(" ({} per iteration, {} constructing tours, {} updating pheromones), {} seconds in total",
+		summary.mean_iteration_seconds, summary.mean_construction_seconds, summary.mean_pheromone_update_seconds, summary.total_seconds));
+	if let (Some(target), Some(success_rate)) = (summary.target, summary.success_rate) {
+		output.push_str(&format!(", {}% of runs reached {}", success_rate * 100.0, target));
+	}
+	if INTERRUPTED.load(Ordering::Relaxed) {
+		output.push_str(". Interrupted, the last runs were cut short");
+	}
+	if let Some(path) = &args.result {
+		let json = serde_json::json!({ "summary": summary, "runs": records });
+		write_result(path, |writer| serde_json::to_writer_pretty(writer, &json).map_err(std::io::Error::from));
+	}
+	return Ok((output, records));
+}
+
+fn run_record(solver: &mut ant_colony::WorldState, args: &RecordArgs, dir_to_write: &Path) -> Result<(), AntError> {
+	let mut history = args.history.map(|_| ant_colony::ConvergenceRecorder::new());
+	let low_color = colorgrad::Color::from_linear_rgba(0.0, 0.0, 1.0, 1.0);
+	let high_color = colorgrad::Color::from_linear_rgba(1.0, 0.0, 0.0, 0.0);
+	let mut observers = (ant_colony::EdgeRecorder::new(), &mut history);
+	solver.do_all_iterations_with_observer(&mut observers)?;
+	let frames = observers.0.to_graphviz_frames(low_color, high_color);
+	let nodes = solver.nodes_to_graphviz();
+	for (index, item) in frames.iter().enumerate() {
+		let output = format!("graph frame{} {{\n\
+			layout = \"neato\"\n\
+			labelloc = \"t\"\n\
+			overlap = \"prism\"\n\
+			label = \"Frame {} of {}. Found solution is {}.\\nPositions not to scale.\"\n\
+			{}\n\n\
+			{}\n\
+			}}
+			", index, index, frames.len(), solver.best_solution_length, nodes, item
+		);
+		std::fs::write(format!("./{}/{}.dot", dir_to_write.display(), index), output)?;
+	}
+	if let (Some(history), Some(format)) = (history, args.history) {
+		write_history(&history, format, dir_to_write);
+	}
+	return Ok(());
+}
+
+fn run_solve(solver: &mut ant_colony::WorldState, args: &SolveArgs, dir_to_write: &Path) -> Result<(), AntError> {
+	let mut history = args.history.map(|_| ant_colony::ConvergenceRecorder::new());
+	if let Some(checkpoint_path) = &args.checkpoint {
+		match solver.do_all_iterations_with_checkpoints(args.checkpoint_every.unwrap_or(100), checkpoint_path, &mut history) {
+			Ok(_) => {},
+			Err(AntError::Io(error)) => eprintln!("Could not save the checkpoint to {}, stopping early: {}", checkpoint_path.display(), error),
+			Err(error) => return Err(error),
 		}
-		eprintln!("Found solution with length {}", solver.best_solution_length);
-		return format!("{}", solver.solution_to_graphviz());
+	} else {
+		solver.do_all_iterations_with_observer(&mut history)?;
+	}
+	if let (Some(history), Some(format)) = (history, args.history) {
+		write_history(&history, format, dir_to_write);
 	}
+	if let Some(path) = &args.result {
+		write_result(path, |writer| solver.run_result().write_json(writer));
+	}
+	return Ok(());
 }
 
-fn read_file(path: &PathBuf) -> Vec<GraphNode> {
-	let mut reader = csv::ReaderBuilder::new().has_headers(false).delimiter(b' ').trim(csv::Trim::All).from_path(path).unwrap();
-	let mut nodes = Vec::<ant_colony::GraphNode>::new();
-	for result in reader.deserialize() {
-		nodes.push(result.unwrap());
+fn write_history(history: &ant_colony::ConvergenceRecorder, format: HistoryFormat, dir_to_write: &Path) {
+	let result = std::fs::create_dir_all(dir_to_write).and_then(|_| {
+		return match format {
+			HistoryFormat::Csv => history.write_csv(std::fs::File::create(dir_to_write.join("history.csv"))?),
+			HistoryFormat::Json => history.write_json(std::fs::File::create(dir_to_write.join("history.json"))?),
+		};
+	});
+	if let Err(error) = result {
+		eprintln!("Could not write the convergence history to {}: {}", dir_to_write.display(), error);
 	}
-	return nodes;
 }
 
-fn read_directory(path: &PathBuf) -> HashMap<String, Vec<GraphNode>> {
+fn write_result<F: FnOnce(std::fs::File) -> std::io::Result<()>>(path: &Path, write: F) {
+	if let Err(error) = std::fs::File::create(path).and_then(write) {
+		eprintln!("Could not write the result to {}: {}", path.display(), error);
+	}
+}
+
+// files that can't be read are reported and left out
+fn read_directory(path: &PathBuf) -> Result<HashMap<String, Vec<GraphNode>>, AntError> {
 	let mut node_map = HashMap::new();
-	for file in std::fs::read_dir(path).unwrap() {
-		let file = file.unwrap();
-		let nodes = read_file(&file.path());
-		node_map.insert(file.file_name().into_string().unwrap(), nodes);
+	for file in std::fs::read_dir(path)? {
+		let file = file?;
+		let filename = file.file_name().to_string_lossy().into_owned();
+		match ant_colony::read_instance_file(&file.path()) {
+			Ok(nodes) => {
+				node_map.insert(filename, nodes);
+			},
+			Err(error) => eprintln!("Skipping file {}: {}", filename, error),
+		}
 	}
-	return node_map;
+	return Ok(node_map);
 }
 
-fn batch_process_files(directory: &PathBuf, config: Config) {
-	let node_map = read_directory(&config.path);
-	if config.try_count.is_some() {
-		// only save statistics
+// returns whether every file was processed
+fn batch_process_files(directory: &Path, task: Task) -> Result<bool, AntError> {
+	let node_map = read_directory(task.solver_args().path.as_ref().unwrap())?;
+	let mut all_succeeded = true;
+	if let Task::Stats(args) = &task {
+		// only save statistics, the records of every file go to one table
+		let mut records = Vec::new();
 		for (filename, nodes) in node_map {
-			let output = process_set_of_nodes(nodes, config.clone(), &"".into()); // won't write anything anyway
-			println!("File {}: {}", filename, output);
+			if INTERRUPTED.load(Ordering::Relaxed) {
+				break;
+			}
+			let result = ant_colony::ConfigData::try_from(&args.solver).map_err(AntError::from)
+				.and_then(|config| ant_colony::WorldState::new(nodes, config))
+				.and_then(|mut solver| {
+					solver.set_interrupt_flag(INTERRUPTED.clone());
+					return run_stats(solver, args, &instance_name(Path::new(&filename)));
+				});
+			match result {
+				Ok((output, file_records)) => {
+					println!("File {}: {}", filename, output);
+					records.extend(file_records);
+				},
+				Err(error) => {
+					eprintln!("File {}: {}", filename, error);
+					all_succeeded = false;
+				},
+			}
+		}
+		if let Some(path) = &args.records {
+			write_result(path, |writer| ant_colony::write_records_csv(&records, writer));
 		}
 	} else {
 		// create directories for each output file
 		let mut threads = Vec::new();
 		for (filename, nodes) in node_map {
-			let directory = directory.clone();
-			let config = config.clone();
+			let directory = directory.to_path_buf();
+			let task = task.clone();
 			threads.push(std::thread::spawn(move || {
 				let directory = format!("{}/{}", directory.display(), filename);
-				std::fs::create_dir(format!("./{}/", directory)).unwrap();
-				let output = process_set_of_nodes(nodes, config, &directory.clone().into());
-				std::fs::write(format!("./{}/solution.dot", directory), output).unwrap();
+				let result = std::fs::create_dir(format!("./{}/", directory)).map_err(AntError::from).and_then(|_| {
+					let output = process_set_of_nodes(nodes, &task, &filename, Path::new(&directory))?;
+					std::fs::write(format!("./{}/solution.{}", directory, task.output_format().extension()), output)?;
+					return Ok(());
+				});
+				if let Err(error) = &result {
+					eprintln!("File {}: {}", filename, error);
+				}
+				return result.is_ok();
 			}));
 		}
 		for thread in threads {
-			thread.join().unwrap();
+			all_succeeded &= thread.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic));
 		}
 	}
+	return Ok(all_succeeded);
+}
+
+fn instance_name(path: &Path) -> String {
+	return path.file_stem().map_or_else(|| path.display().to_string(), |stem| stem.to_string_lossy().into_owned());
+}
+
+// runs a task on a single file or a whole directory, printing the output of single files
+fn run_task(task: Task) -> Result<bool, String> {
+	if task.solver_args().batch {
+		return batch_process_files(Path::new("output"), task).map_err(|error| error.to_string());
+	}
+	let path = task.solver_args().path.clone().unwrap();
+	let output = ant_colony::read_instance_file(&path)
+		.and_then(|nodes| process_set_of_nodes(nodes, &task, &instance_name(&path), Path::new("output")))
+		.map_err(|error| format!("{}: {}", path.display(), error))?;
+	println!("{}", output.trim_end());
+	return Ok(true);
+}
+
+fn run_exact(args: ExactArgs) -> Result<bool, String> {
+	let nodes = ant_colony::read_instance_file(&args.path).map_err(|error| format!("{}: {}", args.path.display(), error))?;
+	if nodes.len() > MAX_EXACT_NODES {
+		return Err(format!("{}: {} nodes are too many to brute force, the limit is {}", args.path.display(), nodes.len(), MAX_EXACT_NODES));
+	}
+	let mut solver = ant_colony::WorldState::new(nodes, ant_colony::ConfigData::default()).map_err(|error| format!("{}: {}", args.path.display(), error))?;
+	solver.do_bruteforce();
+	eprintln!("Shortest tour has length {}", solver.best_solution_length);
+	println!("{}", args.output_format.format_solution(&solver, &instance_name(&args.path)).trim_end());
+	return Ok(true);
+}
+
+fn run_generate(args: GenerateArgs) -> Result<bool, String> {
+	let seed = args.seed.unwrap_or_else(|| rand::thread_rng().gen());
+	let nodes = ant_colony::generate_instance(args.nodes, args.max_coordinate, seed).map_err(|error| error.to_string())?;
+	let result = match &args.output {
+		Some(path) => std::fs::File::create(path).map_err(AntError::from).and_then(|file| ant_colony::write_instance(&nodes, file)),
+		None => ant_colony::write_instance(&nodes, std::io::stdout().lock()),
+	};
+	result.map_err(|error| format!("Could not write the instance: {}", error))?;
+	eprintln!("Generated {} nodes with seed {}", nodes.len(), seed);
+	return Ok(true);
 }
 
-fn main() {
-	let config = Config::parse();
-	if config.batch {
-		batch_process_files(&"output".into(), config);
+fn run_evaluate(args: EvaluateArgs) -> Result<bool, String> {
+	let nodes = ant_colony::read_instance_file(&args.path).map_err(|error| format!("{}: {}", args.path.display(), error))?;
+	let format = match args.tour_format {
+		Some(TourFormatArg::Tsplib) => ant_colony::TourFormat::Tsplib,
+		Some(TourFormatArg::Csv) => ant_colony::TourFormat::Csv,
+		Some(TourFormatArg::Json) => ant_colony::TourFormat::Json,
+		Some(TourFormatArg::List) => ant_colony::TourFormat::List,
+		None => ant_colony::TourFormat::from_path(&args.tour),
+	};
+	let tour = std::fs::File::open(&args.tour).map_err(AntError::from)
+		.and_then(|file| ant_colony::read_tour(file, format, &nodes))
+		.map_err(|error| format!("{}: {}", args.tour.display(), error))?;
+	let objective = match args.objective {
+		ObjectiveArg::Open => ant_colony::Objective::OpenPath,
+		ObjectiveArg::Closed => ant_colony::Objective::ClosedTour,
+	};
+	let evaluation = ant_colony::evaluate_tour(&nodes, &tour, objective);
+	if args.json {
+		println!("{}", serde_json::to_string_pretty(&evaluation).map_err(|error| error.to_string())?);
+		return Ok(evaluation.is_valid());
+	}
+	let list = |ids: &[ant_colony::NodeId]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(", ");
+	println!("Tour length is {} ({})", evaluation.length, match objective {
+		ant_colony::Objective::OpenPath => "open path",
+		ant_colony::Objective::ClosedTour => "closed tour",
+	});
+	println!("Visits {} of {} nodes", evaluation.visited, evaluation.node_count);
+	if !evaluation.duplicates.is_empty() {
+		println!("Visited more than once: {}", list(&evaluation.duplicates));
+	}
+	if !evaluation.missing.is_empty() {
+		println!("Missing: {}", list(&evaluation.missing));
+	}
+	if !evaluation.unknown.is_empty() {
+		println!("Not in the instance: {}", list(&evaluation.unknown));
+	}
+	println!("{}", if evaluation.is_valid() { "The tour is valid" } else { "The tour is not valid" });
+	return Ok(evaluation.is_valid());
+}
+
+// instances are named by their file name without the extension, like in the records of `stats`
+fn read_sweep_instances(path: &PathBuf) -> Result<Vec<(String, Vec<GraphNode>)>, String> {
+	if path.is_dir() {
+		let mut instances = read_directory(path).map_err(|error| format!("{}: {}", path.display(), error))?.into_iter()
+			.map(|(filename, nodes)| (instance_name(Path::new(&filename)), nodes)).collect::<Vec<_>>();
+		instances.sort_by(|first, second| first.0.cmp(&second.0));
+		return Ok(instances);
+	}
+	let nodes = ant_colony::read_instance_file(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+	return Ok(vec![(instance_name(path), nodes)]);
+}
+
+fn run_sweep(mut args: SweepArgs) -> Result<bool, String> {
+	if let Some(path) = args.config.clone() {
+		args.merge(SweepFile::load(&path).map_err(|error| format!("Could not read the config file {}: {}", path.display(), error))?);
+	}
+	// picked here instead of in the library so it can be printed and the sweep repeated with --seed
+	let seed = *args.seed.get_or_insert_with(|| rand::thread_rng().gen_range(0..=i64::MAX as u64));
+	let sets = args.check().and_then(|_| args.parameter_sets()).unwrap_or_else(|error| error.exit());
+	let instances = read_sweep_instances(args.path.as_ref().unwrap())?;
+	if instances.is_empty() {
+		return Err(format!("{}: there are no instances to run", args.path.as_ref().unwrap().display()));
+	}
+	let run_count = args.try_count.unwrap() as usize;
+	eprintln!("Running {} parameter sets on {} instances, {} runs each, with seed {}", sets.len(), instances.len(), run_count, seed);
+	install_interrupt_handler();
+	let start = std::time::Instant::now();
+	let records = ant_colony::run_sweep(&instances, &sets, run_count, args.threads.unwrap_or_else(num_cpus::get), Some(INTERRUPTED.clone())).map_err(|error| error.to_string())?;
+	let wall_seconds = start.elapsed().as_secs_f64();
+	let result = match &args.output {
+		Some(path) => std::fs::File::create(path).and_then(|file| ant_colony::write_records_csv(&records, file)),
+		None => ant_colony::write_records_csv(&records, std::io::stdout().lock()),
+	};
+	result.map_err(|error| format!("Could not write the results: {}", error))?;
+
+	// the instances are the same for every set, so the average length over all runs ranks them
+	let mut lengths = vec![Vec::new(); sets.len()];
+	for record in &records {
+		lengths[record.set].push(record.length);
+	}
+	let best = lengths.iter().enumerate().filter(|(_, lengths)| !lengths.is_empty())
+		.map(|(set, lengths)| (set, lengths.iter().sum::<f64>() / lengths.len() as f64))
+		.min_by(|first, second| first.1.total_cmp(&second.1));
+	eprintln!("Finished {} runs in {} seconds", records.len(), wall_seconds);
+	if let Some((set, average)) = best {
+		let record = records.iter().find(|record| record.set == set).unwrap();
+		eprintln!("Lowest average length {} with set {}: variant {}, ant count {}, evaporation coefficient {}, random choice chance {}, pheromone weight {}, heuristic weight {}",
+			average, set, record.variant, record.ant_count, record.pheromone_evaporation_coefficient, record.random_choice_chance, record.pheromone_weight, record.heuristic_weight);
+	}
+	if INTERRUPTED.load(Ordering::Relaxed) {
+		eprintln!("Interrupted, the results are incomplete");
+	}
+	return Ok(true);
+}
+
+fn run_tune(mut args: TuneArgs) -> Result<bool, String> {
+	if let Some(path) = args.config.clone() {
+		args.merge(TuneFile::load(&path).map_err(|error| format!("Could not read the config file {}: {}", path.display(), error))?);
+	}
+	let (parameters, base) = args.check().and_then(|_| args.parameters()).unwrap_or_else(|error| error.exit());
+	let instances = read_sweep_instances(args.path.as_ref().unwrap())?;
+	if instances.is_empty() {
+		return Err(format!("{}: there are no instances to tune on", args.path.as_ref().unwrap().display()));
+	}
+	let seed = args.seed.unwrap_or_else(|| rand::thread_rng().gen());
+	let thread_count = args.threads.unwrap_or_else(num_cpus::get);
+	let configure = |values: &[ant_colony::ParameterValue]| {
+		let mut args = base.clone();
+		for (parameter, value) in parameters.iter().zip(values) {
+			set_tuned_value(&mut args, &parameter.name, value);
+		}
+		return args;
+	};
+	let run = |args: &SolverArgs, instance: usize, seed: u64| -> Result<f64, AntError> {
+		let config = ant_colony::ConfigData { seed: Some(seed), ..ant_colony::ConfigData::try_from(args)? };
+		let mut solver = ant_colony::WorldState::new(instances[instance].1.clone(), config)?;
+		solver.set_interrupt_flag(INTERRUPTED.clone());
+		solver.do_all_iterations()?;
+		return Ok(solver.best_solution_length);
+	};
+	eprintln!("Tuning {} parameters on {} instances, seed {}", parameters.len(), instances.len(), seed);
+	install_interrupt_handler();
+
+	let method = args.method.unwrap_or(TuneMethod::Race);
+	let (configurations, iterations) = if let TuneMethod::Race = method {
+		let settings = ant_colony::RaceSettings {
+			max_experiments: args.budget.unwrap_or(1000),
+			first_test: args.first_test.unwrap_or(5),
+			seed,
+			thread_count,
+			interrupt_flag: Some(INTERRUPTED.clone()),
+		};
+		let result = ant_colony::iterated_race(&parameters, instances.len(), &settings, |values, instance, seed| run(&configure(values), instance, seed))
+			.map_err(|error| error.to_string())?;
+		eprintln!("Finished {} iterations with {} runs", result.iterations, result.experiments);
+		(result.elites, None)
 	} else {
-		let nodes = read_file(&config.path);
-		let output = process_set_of_nodes(nodes, config, &"output".into());
-		println!("{}", output);
+		let settings = ant_colony::SearchSettings { seed, ..args.search_settings() };
+		let evaluate = |values: &[ant_colony::ParameterValue], evaluation: &ant_colony::Evaluation| {
+			let mut args = configure(values);
+			args.iterations = evaluation.iterations.or(args.iterations);
+			return run(&args, evaluation.instance, evaluation.seed);
+		};
+		let result = match method {
+			TuneMethod::Random => ant_colony::random_search(&parameters, instances.len(), args.configurations.unwrap_or(50), &settings, evaluate),
+			TuneMethod::Halving => ant_colony::successive_halving(&parameters, instances.len(), args.configurations.unwrap_or(27), &settings, evaluate),
+			_ => ant_colony::hyperband(&parameters, instances.len(), &settings, evaluate),
+		}.map_err(|error| error.to_string())?;
+		eprintln!("Finished {} brackets with {} runs, the best configurations got {} {}", result.brackets, result.evaluations, result.resource, match settings.resource {
+			ant_colony::BudgetResource::Iterations => "iterations",
+			ant_colony::BudgetResource::Runs => "runs per instance",
+		});
+		let iterations = match settings.resource {
+			ant_colony::BudgetResource::Iterations => Some(result.resource as u32),
+			ant_colony::BudgetResource::Runs => None,
+		};
+		(result.configurations, iterations)
+	};
+	if INTERRUPTED.load(Ordering::Relaxed) {
+		eprintln!("Interrupted, the configurations are the best ones so far");
+	}
+	let Some(best) = configurations.first() else {
+		return Err("The budget is too small to compare any configurations".to_string());
+	};
+	for (rank, configuration) in configurations.iter().enumerate().take(10) {
+		let values = parameters.iter().zip(&configuration.values).map(|(parameter, value)| format!("{} {}", parameter.name, value)).collect::<Vec<_>>().join(", ");
+		eprintln!("{}. {}: average length {} over {} runs, average rank {}", rank + 1, values, configuration.mean_length, configuration.runs, configuration.mean_rank);
+	}
+	let mut best_args = configure(&best.values);
+	// tuned on that many iterations
+	best_args.iterations = iterations.or(best_args.iterations);
+	best_args.termination_mode.get_or_insert(TerminationModeArg::Any);
+	best_args.variant.get_or_insert(VariantArg::AntSystem);
+	let file = best_args.to_file();
+	return match &args.output {
+		Some(path) => file.save(path).map(|_| true).map_err(|error| format!("Could not write the config to {}: {}", path.display(), error)),
+		None => {
+			print!("{}", toml::to_string_pretty(&file).map_err(|error| error.to_string())?);
+			Ok(true)
+		},
+	};
+}
+
+fn install_interrupt_handler() {
+	let result = ctrlc::set_handler(|| {
+		if INTERRUPTED.swap(true, Ordering::Relaxed) {
+			// second signal, don't wait for the iteration to finish
+			std::process::exit(130);
+		}
+		eprintln!("Interrupted, finishing the current iteration. Press Ctrl-C again to abort immediately");
+	});
+	if let Err(error) = result {
+		eprintln!("Could not install the interrupt handler, Ctrl-C will abort immediately: {}", error);
+	}
+}
+
+// merges the config file, checks the options and dumps the effective config if asked to
+fn prepare_task(mut task: Task) -> Result<Task, String> {
+	let file = task.solver_args().load_config()?;
+	let check = match &mut task {
+		Task::Solve(args) => {
+			args.merge(&file);
+			args.check()
+		},
+		Task::Stats(args) => {
+			args.merge(&file);
+			args.check()
+		},
+		Task::Record(args) => {
+			args.merge(&file);
+			args.solver.check()
+		},
+	};
+	if let Err(error) = check {
+		error.exit();
 	}
-}
\ No newline at end of file
+	match &mut task {
+		Task::Solve(args) => args.solver.dump(args.to_file())?,
+		Task::Stats(args) => args.solver.dump(args.to_file())?,
+		Task::Record(args) => args.solver.dump(args.to_file())?,
+	}
+	return Ok(task);
+}
+
+fn main() -> ExitCode {
+	let cli = Cli::parse();
+	let result = match cli.command {
+		Command::Solve(args) if args.resume.is_some() => {
+			let checkpoint_path = args.resume.clone().unwrap();
+			install_interrupt_handler();
+			ant_colony::WorldState::load_checkpoint(&checkpoint_path).and_then(|solver| {
+				eprintln!("Resuming from iteration {}", solver.current_iteration);
+				let output = process_solver(solver, &Task::Solve(args), &instance_name(&checkpoint_path), Path::new("output"))?;
+				println!("{}", output.trim_end());
+				return Ok(true);
+			}).map_err(|error| format!("{}: {}", checkpoint_path.display(), error))
+		},
+		Command::Solve(args) => prepare_task(Task::Solve(args)).and_then(|task| {
+			install_interrupt_handler();
+			return run_task(task);
+		}),
+		Command::Stats(args) => prepare_task(Task::Stats(args)).and_then(|task| {
+			install_interrupt_handler();
+			return run_task(task);
+		}),
+		Command::Record(args) => prepare_task(Task::Record(args)).and_then(|task| {
+			install_interrupt_handler();
+			return run_task(task);
+		}),
+		Command::Sweep(args) => run_sweep(args),
+		Command::Tune(args) => run_tune(args),
+		Command::Exact(args) => run_exact(args),
+		Command::Generate(args) => run_generate(args),
+		Command::Evaluate(args) => run_evaluate(args),
+	};
+	return match result {
+		Ok(true) => ExitCode::SUCCESS,
+		Ok(false) => ExitCode::FAILURE,
+		Err(error) => {
+			eprintln!("{}", error);
+			ExitCode::FAILURE
+		},
+	};
+}
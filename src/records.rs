@@ -0,0 +1,199 @@
+use std::io::{Read, Write};
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::{AntError, ConfigData, RunRecord};
+
+// One run with the instance and parameters it ran with, flat so every record is one row of a table.
+// `stats --records`, `sweep` and `stat_collector` all use this schema
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExperimentRecord {
+	pub set: usize, // index of the parameter set, 0 outside of a sweep
+	pub instance: String,
+	pub variant: String,
+	pub ant_count: usize,
+	pub max_iterations: Option<u32>,
+	pub time_limit: Option<f64>, // seconds
+	pub target_length: Option<f64>,
+	pub stagnation_limit: Option<u32>,
+	pub pheromone_evaporation_coefficient: f64,
+	pub random_choice_chance: f64,
+	pub pheromone_weight: f64,
+	pub heuristic_weight: f64,
+	pub run: usize,
+	pub thread: usize,
+	pub seed: u64,
+	pub length: f64,
+	pub best_iteration: u32,
+	pub iterations: u32,
+	pub runtime_seconds: f64,
+	pub mean_iteration_seconds: f64,
+	pub construction_seconds: f64,
+	pub best_update_seconds: f64,
+	pub pheromone_update_seconds: f64,
+}
+
+// the CSV header, in field order
+pub const RECORD_COLUMNS: [&str; 23] = [
+	"set", "instance", "variant", "ant_count", "max_iterations", "time_limit", "target_length", "stagnation_limit",
+	"pheromone_evaporation_coefficient", "random_choice_chance", "pheromone_weight", "heuristic_weight",
+	"run", "thread", "seed", "length", "best_iteration", "iterations", "runtime_seconds",
+	"mean_iteration_seconds", "construction_seconds", "best_update_seconds", "pheromone_update_seconds",
+];
+
+impl ExperimentRecord {
+	pub fn new(set: usize, instance: &str, config: &ConfigData, run: &RunRecord) -> Self {
+		return Self {
+			set,
+			instance: instance.to_string(),
+			variant: config.variant.name().to_string(),
+			ant_count: config.ant_count,
+			max_iterations: config.termination.max_iterations,
+			time_limit: config.termination.time_limit.map(|limit| limit.as_secs_f64()),
+			target_length: config.termination.target_length,
+			stagnation_limit: config.termination.stagnation_limit,
+			pheromone_evaporation_coefficient: config.pheromone_evaporation_coefficient,
+			random_choice_chance: config.random_choice_chance,
+			pheromone_weight: config.pheromone_weight,
+			heuristic_weight: config.heuristic_weight,
+			run: run.run,
+			thread: run.thread,
+			seed: run.seed,
+			length: run.length,
+			best_iteration: run.best_iteration,
+			iterations: run.iterations,
+			runtime_seconds: run.runtime_seconds,
+			mean_iteration_seconds: run.mean_iteration_seconds,
+			construction_seconds: run.construction_seconds,
+			best_update_seconds: run.best_update_seconds,
+			pheromone_update_seconds: run.pheromone_update_seconds,
+		};
+	}
+
+	// the run without the instance and parameters
+	pub fn run_record(&self) -> RunRecord {
+		return RunRecord {
+			run: self.run,
+			thread: self.thread,
+			seed: self.seed,
+			length: self.length,
+			best_iteration: self.best_iteration,
+			iterations: self.iterations,
+			runtime_seconds: self.runtime_seconds,
+			mean_iteration_seconds: self.mean_iteration_seconds,
+			construction_seconds: self.construction_seconds,
+			best_update_seconds: self.best_update_seconds,
+			pheromone_update_seconds: self.pheromone_update_seconds,
+		};
+	}
+
+	// what the types can't check
+	fn validate(&self) -> Result<(), String> {
+		if self.instance.is_empty() {
+			return Err("the instance is empty".to_string());
+		}
+		if !(self.length.is_finite() && self.length >= 0.0) {
+			return Err(format!("the length has to be a finite number of at least 0, got {}", self.length));
+		}
+		let timings = [
+			("runtime", self.runtime_seconds),
+			("mean iteration time", self.mean_iteration_seconds),
+			("construction time", self.construction_seconds),
+			("best update time", self.best_update_seconds),
+			("pheromone update time", self.pheromone_update_seconds),
+		];
+		if let Some((name, value)) = timings.into_iter().find(|(_, value)| !(value.is_finite() && *value >= 0.0)) {
+			return Err(format!("the {} has to be a finite number of at least 0, got {}", name, value));
+		}
+		if self.best_iteration > self.iterations {
+			return Err(format!("the best tour was found at iteration {}, after the last one ({})", self.best_iteration, self.iterations));
+		}
+		return Ok(());
+	}
+}
+
+// one row per record
+pub fn write_records_csv<W: Write>(records: &[ExperimentRecord], writer: W) -> std::io::Result<()> {
+	let mut writer = csv::Writer::from_writer(writer);
+	for record in records {
+		writer.serialize(record)?;
+	}
+	writer.flush()?;
+	return Ok(());
+}
+
+// The header has to have exactly the columns of `RECORD_COLUMNS`, in any order
+pub fn read_records_csv<R: Read>(reader: R) -> Result<Vec<ExperimentRecord>, AntError> {
+	let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(reader);
+	let to_parse_error = |error: csv::Error, headers: Option<&csv::StringRecord>| {
+		let line = error.position().map_or(0, |position| position.line() as usize);
+		return match error.into_kind() {
+			csv::ErrorKind::Io(error) => AntError::Io(error),
+			csv::ErrorKind::Deserialize { err, .. } => {
+				// name the column instead of its index
+				let column = err.field().and_then(|field| headers?.get(field as usize));
+				let message = match column {
+					Some(column) => format!("column {}: {}", column, err.kind()),
+					None => err.kind().to_string(),
+				};
+				AntError::Parse { line, message }
+			},
+			kind => AntError::Parse { line, message: format!("{:?}", kind) },
+		};
+	};
+	let headers = reader.headers().map_err(|error| to_parse_error(error, None))?.clone();
+	let missing = RECORD_COLUMNS.iter().filter(|column| !headers.iter().any(|header| header == **column)).copied().collect::<Vec<_>>();
+	let unknown = headers.iter().filter(|header| !RECORD_COLUMNS.contains(header)).collect::<Vec<_>>();
+	if !missing.is_empty() || !unknown.is_empty() {
+		let mut problems = Vec::new();
+		if !missing.is_empty() {
+			problems.push(format!("missing columns {}", missing.join(", ")));
+		}
+		if !unknown.is_empty() {
+			problems.push(format!("unknown columns {}", unknown.join(", ")));
+		}
+		return Err(AntError::Parse { line: 1, message: format!("the header doesn't match the record schema: {}", problems.join(", ")) });
+	}
+	let mut records = Vec::new();
+	for result in reader.deserialize() {
+		let record: ExperimentRecord = result.map_err(|error| to_parse_error(error, Some(&headers)))?;
+		record.validate().map_err(|message| AntError::Parse { line: records.len() + 2, message })?;
+		records.push(record);
+	}
+	return Ok(records);
+}
+
+#[derive(Deserialize)]
+struct RecordsObject {
+	runs: Vec<ExperimentRecord>,
+}
+
+// An array of records, or an object with them as `runs` like `stats --result` writes
+pub fn read_records_json<R: Read>(mut reader: R) -> Result<Vec<ExperimentRecord>, AntError> {
+	let mut text = String::new();
+	reader.read_to_string(&mut text)?;
+	// serde_json ends its messages with the position, the line is already part of the error
+	let to_parse_error = |error: serde_json::Error| {
+		let message = error.to_string();
+		let message = message.rsplit_once(" at line ").map_or(message.as_str(), |(message, _)| message).to_string();
+		return AntError::Parse { line: error.line(), message };
+	};
+	let records = match text.trim_start().chars().next() {
+		Some('[') => serde_json::from_str::<Vec<ExperimentRecord>>(&text).map_err(to_parse_error)?,
+		Some('{') => serde_json::from_str::<RecordsObject>(&text).map_err(to_parse_error)?.runs,
+		_ => return Err(AntError::Parse { line: 1, message: "expected an array of records or an object with runs".to_string() }),
+	};
+	for (index, record) in records.iter().enumerate() {
+		record.validate().map_err(|message| AntError::Parse { line: 0, message: format!("record {}: {}", index + 1, message) })?;
+	}
+	return Ok(records);
+}
+
+// JSON by the extension, CSV otherwise
+pub fn read_records_file(path: &Path) -> Result<Vec<ExperimentRecord>, AntError> {
+	let file = std::fs::File::open(path)?;
+	return match path.extension().is_some_and(|extension| extension == "json") {
+		true => read_records_json(file),
+		false => read_records_csv(file),
+	};
+}
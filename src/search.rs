@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use rand::prelude::*;
+use rand_xoshiro::Xoshiro256PlusPlus;
+use serde::{Deserialize, Serialize};
+use crate::stats::{run_jobs, FriedmanTest};
+use crate::tune::sample_uniform;
+use crate::{run_seed, AntError, ParameterValue, TunedConfiguration, TuningParameter};
+
+// What the budget of a configuration is counted in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BudgetResource {
+	Iterations, // every run stops after that many iterations
+	Runs, // rounds of one run per instance, each round with its own seed
+}
+
+// One run `evaluate` has to do, `iterations` overrides the iteration limit when the budget is counted in iterations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Evaluation {
+	pub instance: usize,
+	pub round: usize,
+	pub seed: u64,
+	pub iterations: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchSettings {
+	pub resource: BudgetResource,
+	pub min_resource: usize,
+	pub max_resource: usize,
+	pub reduction_factor: usize, // successive halving keeps the best 1 / reduction_factor of the configurations
+	pub repeats: usize, // rounds per instance when the budget is counted in iterations
+	pub seed: u64,
+	pub thread_count: usize,
+	pub interrupt_flag: Option<Arc<AtomicBool>>, // stops the search after the current runs, the results so far are kept
+}
+
+impl Default for SearchSettings {
+	fn default() -> Self {
+		return Self {
+			resource: BudgetResource::Runs,
+			min_resource: 1,
+			max_resource: 9,
+			reduction_factor: 3,
+			repeats: 1,
+			seed: 0,
+			thread_count: 1,
+			interrupt_flag: None,
+		};
+	}
+}
+
+impl SearchSettings {
+	// a reduction factor below 2 would never shrink the budget, and every budget needs at least one run
+	pub fn validate(&self) -> Result<(), AntError> {
+		for (name, value, minimum) in [
+			("reduction factor", self.reduction_factor, 2),
+			("minimum resource", self.min_resource, 1),
+			("maximum resource", self.max_resource, self.min_resource.max(1)),
+			("number of repeats", self.repeats, 1),
+		] {
+			if value < minimum {
+				return Err(AntError::InvalidSearchSetting { name, value, minimum });
+			}
+		}
+		return Ok(());
+	}
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResult {
+	pub configurations: Vec<TunedConfiguration>, // the ones that got the largest budget, best first
+	pub resource: usize, // that budget
+	pub evaluations: usize, // runs over the whole search
+	pub brackets: usize,
+}
+
+struct Searcher<'a, F> {
+	parameters: &'a [TuningParameter],
+	instance_count: usize,
+	settings: &'a SearchSettings,
+	evaluate: F,
+	random_source: Xoshiro256PlusPlus,
+	candidates: Vec<(Vec<ParameterValue>, HashMap<Evaluation, f64>)>,
+	rungs: Vec<(usize, Vec<usize>)>, // finished rungs by their budget, with the candidates that ran in them
+	evaluations: usize,
+	brackets: usize,
+}
+
+impl<'a, F> Searcher<'a, F>
+where
+	F: Fn(&[ParameterValue], &Evaluation) -> Result<f64, AntError> + Sync,
+{
+	fn new(parameters: &'a [TuningParameter], instance_count: usize, settings: &'a SearchSettings, evaluate: F) -> Result<Self, AntError> {
+		if instance_count == 0 {
+			return Err(AntError::EmptyInstance);
+		}
+		settings.validate()?;
+		return Ok(Self {
+			parameters,
+			instance_count,
+			settings,
+			evaluate,
+			random_source: Xoshiro256PlusPlus::seed_from_u64(settings.seed),
+			candidates: Vec::new(),
+			rungs: Vec::new(),
+			evaluations: 0,
+			brackets: 0,
+		});
+	}
+
+	fn is_interrupted(&self) -> bool {
+		return self.settings.interrupt_flag.as_ref().is_some_and(|flag| flag.load(Ordering::Relaxed));
+	}
+
+	// the same for every candidate, so round `i` of a larger budget repeats round `i` of a smaller one
+	fn evaluations_for(&self, resource: usize) -> Vec<Evaluation> {
+		let (rounds, iterations) = match self.settings.resource {
+			BudgetResource::Iterations => (self.settings.repeats, Some(resource as u32)),
+			BudgetResource::Runs => (resource, None),
+		};
+		return (0..rounds).flat_map(|round| (0..self.instance_count).map(move |instance| Evaluation { instance, round, seed: run_seed(self.settings.seed, round), iterations })).collect();
+	}
+
+	// returns the candidates best first, or `None` if the search got interrupted
+	fn run_rung(&mut self, candidates: &[usize], resource: usize) -> Result<Option<Vec<usize>>, AntError> {
+		let evaluations = self.evaluations_for(resource);
+		let pending = candidates.iter().flat_map(|candidate| evaluations.iter().map(move |evaluation| (*candidate, *evaluation)))
+			.filter(|(candidate, evaluation)| !self.candidates[*candidate].1.contains_key(evaluation)).collect::<Vec<_>>();
+		let lengths = run_jobs(pending.len(), self.settings.thread_count, || self.is_interrupted(), |job, _| {
+			let (candidate, evaluation) = pending[job];
+			return (self.evaluate)(&self.candidates[candidate].0, &evaluation).map(Some);
+		})?;
+		if lengths.len() < pending.len() || self.is_interrupted() {
+			return Ok(None); // runs cut short can't be compared with the others
+		}
+		self.evaluations += pending.len();
+		for ((candidate, evaluation), length) in pending.into_iter().zip(lengths) {
+			self.candidates[candidate].1.insert(evaluation, length);
+		}
+		self.rungs.push((resource, candidates.to_vec()));
+		let ranks = self.mean_ranks(candidates, resource);
+		let mut ranked = candidates.iter().copied().zip(ranks).collect::<Vec<_>>();
+		ranked.sort_by(|first, second| first.1.total_cmp(&second.1));
+		return Ok(Some(ranked.into_iter().map(|(candidate, _)| candidate).collect()));
+	}
+
+	// ranked within every single run of the rung
+	fn mean_ranks(&self, candidates: &[usize], resource: usize) -> Vec<f64> {
+		let evaluations = self.evaluations_for(resource);
+		let blocks = evaluations.iter().map(|evaluation| candidates.iter().map(|candidate| self.candidates[*candidate].1[evaluation]).collect()).collect::<Vec<_>>();
+		return FriedmanTest::new(&blocks).rank_sums.into_iter().map(|rank_sum| rank_sum / evaluations.len() as f64).collect();
+	}
+
+	// Successive halving: `count` new candidates start at `resources[0]`, after every rung the best 1 / reduction factor
+	// move on to the next budget. Returns false if the search got interrupted
+	fn bracket(&mut self, count: usize, resources: &[usize]) -> Result<bool, AntError> {
+		self.brackets += 1;
+		let mut candidates = (0..count).map(|_| {
+			self.candidates.push((sample_uniform(self.parameters, &mut self.random_source), HashMap::new()));
+			return self.candidates.len() - 1;
+		}).collect::<Vec<_>>();
+		for (rung, resource) in resources.iter().enumerate() {
+			let Some(ranked) = self.run_rung(&candidates, *resource)? else {
+				return Ok(false);
+			};
+			let keep = if rung + 1 == resources.len() { ranked.len() } else { (ranked.len() / self.settings.reduction_factor).max(1) };
+			candidates = ranked[..keep].to_vec();
+		}
+		return Ok(true);
+	}
+
+	// budgets from max_resource / reduction_factor^(rung_count - 1) up to max_resource, at most as many as fit above min_resource
+	fn resources(&self, rung_count: usize) -> Vec<usize> {
+		let factor = self.settings.reduction_factor;
+		return (0..rung_count).rev().map(|rung| (self.settings.max_resource / factor.pow(rung as u32)).max(self.settings.min_resource)).collect();
+	}
+
+	fn max_rung_count(&self) -> usize {
+		let (mut count, mut resource) = (1, self.settings.max_resource);
+		while resource / self.settings.reduction_factor >= self.settings.min_resource {
+			resource /= self.settings.reduction_factor;
+			count += 1;
+		}
+		return count;
+	}
+
+	// every candidate that made it to the largest budget any rung finished, over all brackets
+	fn finish(self) -> SearchResult {
+		let Some(resource) = self.rungs.iter().map(|(resource, _)| *resource).max() else {
+			return SearchResult { configurations: Vec::new(), resource: 0, evaluations: self.evaluations, brackets: self.brackets };
+		};
+		let mut finalists = self.rungs.iter().filter(|(rung_resource, _)| *rung_resource == resource).flat_map(|(_, candidates)| candidates.iter().copied()).collect::<Vec<_>>();
+		finalists.sort_unstable();
+		finalists.dedup();
+		let ranks = self.mean_ranks(&finalists, resource);
+		let evaluations = self.evaluations_for(resource);
+		let mut configurations = finalists.iter().zip(ranks).map(|(candidate, mean_rank)| {
+			let (values, results) = &self.candidates[*candidate];
+			return TunedConfiguration {
+				values: values.clone(),
+				runs: evaluations.len(),
+				mean_length: evaluations.iter().map(|evaluation| results[evaluation]).sum::<f64>() / evaluations.len() as f64,
+				mean_rank,
+			};
+		}).collect::<Vec<_>>();
+		configurations.sort_by(|first, second| first.mean_rank.total_cmp(&second.mean_rank).then(first.mean_length.total_cmp(&second.mean_length)));
+		return SearchResult { configurations, resource, evaluations: self.evaluations, brackets: self.brackets };
+	}
+}
+
+// Evaluates `count` uniformly sampled configurations with the full budget
+pub fn random_search<F>(parameters: &[TuningParameter], instance_count: usize, count: usize, settings: &SearchSettings, evaluate: F) -> Result<SearchResult, AntError>
+where
+	F: Fn(&[ParameterValue], &Evaluation) -> Result<f64, AntError> + Sync,
+{
+	if count == 0 {
+		return Err(AntError::InvalidSearchSetting { name: "number of configurations", value: count, minimum: 1 });
+	}
+	let mut searcher = Searcher::new(parameters, instance_count, settings, evaluate)?;
+	searcher.bracket(count, &[settings.max_resource])?;
+	return Ok(searcher.finish());
+}
+
+// One bracket of successive halving over `count` configurations, with as many rungs as fit between the budgets
+pub fn successive_halving<F>(parameters: &[TuningParameter], instance_count: usize, count: usize, settings: &SearchSettings, evaluate: F) -> Result<SearchResult, AntError>
+where
+	F: Fn(&[ParameterValue], &Evaluation) -> Result<f64, AntError> + Sync,
+{
+	if count == 0 {
+		return Err(AntError::InvalidSearchSetting { name: "number of configurations", value: count, minimum: 1 });
+	}
+	let mut searcher = Searcher::new(parameters, instance_count, settings, evaluate)?;
+	let resources = searcher.resources(searcher.max_rung_count());
+	searcher.bracket(count, &resources)?;
+	return Ok(searcher.finish());
+}
+
+// Hyperband: brackets of successive halving from many configurations on a small budget to a few on the full one
+pub fn hyperband<F>(parameters: &[TuningParameter], instance_count: usize, settings: &SearchSettings, evaluate: F) -> Result<SearchResult, AntError>
+where
+	F: Fn(&[ParameterValue], &Evaluation) -> Result<f64, AntError> + Sync,
+{
+	let mut searcher = Searcher::new(parameters, instance_count, settings, evaluate)?;
+	let rung_count = searcher.max_rung_count();
+	let factor = settings.reduction_factor as f64;
+	for rungs in (1..=rung_count).rev() {
+		let count = (rung_count as f64 / rungs as f64 * factor.powi(rungs as i32 - 1)).ceil() as usize;
+		let resources = searcher.resources(rungs);
+		if !searcher.bracket(count, &resources)? {
+			break;
+		}
+	}
+	return Ok(searcher.finish());
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ParameterDomain;
+
+	fn parameters() -> Vec<TuningParameter> {
+		return vec![TuningParameter { name: "x".to_string(), domain: ParameterDomain::Real { min: 0.0, max: 1.0 } }];
+	}
+
+	// shorter for smaller x, so the search has something to find
+	fn evaluate(values: &[ParameterValue], _: &Evaluation) -> Result<f64, AntError> {
+		return match values[0] {
+			ParameterValue::Real(x) => Ok(x),
+			_ => unreachable!(),
+		};
+	}
+
+	fn assert_rejected(settings: SearchSettings, expected_name: &str) {
+		for result in [
+			random_search(&parameters(), 1, 3, &settings, evaluate),
+			successive_halving(&parameters(), 1, 3, &settings, evaluate),
+			hyperband(&parameters(), 1, &settings, evaluate),
+		] {
+			assert!(matches!(result, Err(AntError::InvalidSearchSetting { name, .. }) if name == expected_name));
+		}
+	}
+
+	#[test]
+	fn reduction_factor_below_two_is_rejected() {
+		assert_rejected(SearchSettings { reduction_factor: 0, ..SearchSettings::default() }, "reduction factor");
+		assert_rejected(SearchSettings { reduction_factor: 1, ..SearchSettings::default() }, "reduction factor");
+	}
+
+	#[test]
+	fn zero_minimum_resource_is_rejected() {
+		assert_rejected(SearchSettings { min_resource: 0, ..SearchSettings::default() }, "minimum resource");
+	}
+
+	#[test]
+	fn maximum_below_minimum_resource_is_rejected() {
+		assert_rejected(SearchSettings { min_resource: 5, max_resource: 4, ..SearchSettings::default() }, "maximum resource");
+	}
+
+	#[test]
+	fn zero_repeats_are_rejected() {
+		assert_rejected(SearchSettings { repeats: 0, ..SearchSettings::default() }, "number of repeats");
+	}
+
+	#[test]
+	fn zero_configurations_are_rejected() {
+		let settings = SearchSettings::default();
+		for result in [random_search(&parameters(), 1, 0, &settings, evaluate), successive_halving(&parameters(), 1, 0, &settings, evaluate)] {
+			assert!(matches!(result, Err(AntError::InvalidSearchSetting { name: "number of configurations", value: 0, minimum: 1 })));
+		}
+	}
+
+	#[test]
+	fn valid_settings_finish() {
+		let settings = SearchSettings { min_resource: 1, max_resource: 9, reduction_factor: 3, ..SearchSettings::default() };
+		let result = hyperband(&parameters(), 2, &settings, evaluate).unwrap();
+		assert_eq!(result.resource, 9);
+		assert_eq!(result.brackets, 3);
+		assert!(!result.configurations.is_empty());
+	}
+}
@@ -0,0 +1,546 @@
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use serde::{Deserialize, Serialize};
+use crate::{AntError, WorldState};
+
+// One finished run, see `WorldState::run_record`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RunRecord {
+	pub run: usize, // index in the batch, 0 outside of one
+	pub thread: usize, // that did the run, 0 outside of a batch
+	pub seed: u64,
+	pub length: f64,
+	pub best_iteration: u32, // iteration that found the tour
+	pub iterations: u32,
+	pub runtime_seconds: f64, // time spent iterating
+	pub mean_iteration_seconds: f64,
+	pub construction_seconds: f64, // the phases of all iterations, see `PhaseTimings`
+	pub best_update_seconds: f64,
+	pub pheromone_update_seconds: f64,
+}
+
+impl WorldState {
+	pub fn run_record(&self) -> RunRecord {
+		return RunRecord {
+			run: 0,
+			thread: 0,
+			seed: self.seed,
+			length: self.best_solution_length,
+			best_iteration: self.best_solution_iteration,
+			iterations: self.current_iteration,
+			runtime_seconds: self.elapsed.as_secs_f64(),
+			mean_iteration_seconds: if self.current_iteration > 0 { self.timings.total().as_secs_f64() / self.current_iteration as f64 } else { 0.0 },
+			construction_seconds: self.timings.construction.as_secs_f64(),
+			best_update_seconds: self.timings.best_update.as_secs_f64(),
+			pheromone_update_seconds: self.timings.pheromone_update.as_secs_f64(),
+		};
+	}
+}
+
+// Every run of a batch, in the order they were added
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BatchRunData {
+	pub runs: Vec<RunRecord>,
+	#[serde(default)]
+	pub wall_seconds: f64, // from the start of the batch to its last run, including setup and waiting for threads
+}
+
+// Summary of the lengths in a `BatchRunData`, all NaN without runs
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct BatchSummary {
+	pub run_count: usize,
+	pub min: f64,
+	pub max: f64,
+	pub mean: f64,
+	pub std_dev: f64, // sample standard deviation, 0 for a single run
+	pub median: f64,
+	pub first_quartile: f64,
+	pub third_quartile: f64,
+	pub confidence_low: f64, // 95% confidence interval of the mean, from the t distribution
+	pub confidence_high: f64,
+	pub mean_best_iteration: f64,
+	pub mean_runtime_seconds: f64,
+	pub mean_iteration_seconds: f64, // over all iterations of all runs
+	pub mean_construction_seconds: f64, // per run
+	pub mean_pheromone_update_seconds: f64,
+	pub total_seconds: f64, // wall-clock time of the whole batch
+	pub target: Option<f64>,
+	pub success_rate: Option<f64>, // share of runs at most `target` long
+}
+
+impl BatchRunData {
+	pub fn new() -> Self {
+		return Self::default();
+	}
+
+	pub fn add_run(&mut self, run: RunRecord) {
+		self.runs.push(run);
+	}
+
+	// for batches run one after the other
+	pub fn add_batch(&mut self, other: Self) {
+		self.runs.extend(other.runs);
+		self.wall_seconds += other.wall_seconds;
+	}
+
+	pub fn run_count(&self) -> usize {
+		return self.runs.len();
+	}
+
+	// one row per run
+	pub fn write_csv<W: Write>(&self, writer: W) -> std::io::Result<()> {
+		let mut writer = csv::Writer::from_writer(writer);
+		for run in &self.runs {
+			writer.serialize(run)?;
+		}
+		writer.flush()?;
+		return Ok(());
+	}
+
+	pub fn summary(&self, target: Option<f64>) -> BatchSummary {
+		let mut lengths = self.runs.iter().map(|run| run.length).collect::<Vec<_>>();
+		lengths.sort_by(f64::total_cmp);
+		let count = lengths.len();
+		let mean = average(&lengths);
+		let std_dev = match count {
+			0 => f64::NAN,
+			1 => 0.0,
+			_ => (lengths.iter().map(|length| (length - mean).powi(2)).sum::<f64>() / (count - 1) as f64).sqrt(),
+		};
+		let margin = if count > 1 { t_critical_975(count - 1) * std_dev / (count as f64).sqrt() } else { f64::NAN };
+		return BatchSummary {
+			run_count: count,
+			min: lengths.first().copied().unwrap_or(f64::NAN),
+			max: lengths.last().copied().unwrap_or(f64::NAN),
+			mean,
+			std_dev,
+			median: quantile(&lengths, 0.5),
+			first_quartile: quantile(&lengths, 0.25),
+			third_quartile: quantile(&lengths, 0.75),
+			confidence_low: mean - margin,
+			confidence_high: mean + margin,
+			mean_best_iteration: average(&self.runs.iter().map(|run| run.best_iteration as f64).collect::<Vec<_>>()),
+			mean_runtime_seconds: average(&self.runs.iter().map(|run| run.runtime_seconds).collect::<Vec<_>>()),
+			mean_iteration_seconds: self.runs.iter().map(|run| run.mean_iteration_seconds * run.iterations as f64).sum::<f64>() / self.runs.iter().map(|run| run.iterations as f64).sum::<f64>(),
+			mean_construction_seconds: average(&self.runs.iter().map(|run| run.construction_seconds).collect::<Vec<_>>()),
+			mean_pheromone_update_seconds: average(&self.runs.iter().map(|run| run.pheromone_update_seconds).collect::<Vec<_>>()),
+			total_seconds: self.wall_seconds,
+			target,
+			success_rate: target.filter(|_| count > 0).map(|target| lengths.iter().filter(|length| **length <= target).count() as f64 / count as f64),
+		};
+	}
+}
+
+// Seed of run `run` of a batch, spread out so batches with neighbouring base seeds don't share runs
+pub fn run_seed(base_seed: u64, run: usize) -> u64 {
+	return base_seed ^ (run as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+}
+
+// Hands out jobs `0..job_count` to up to `thread_count` threads, each taking the next job as soon as it is free.
+// Jobs returning `None` leave no result, once `stop` returns true no more jobs are started. Results come back in job order
+pub(crate) fn run_jobs<T, S, F>(job_count: usize, thread_count: usize, stop: S, job: F) -> Result<Vec<T>, AntError>
+where
+	T: Send,
+	S: Fn() -> bool + Sync,
+	F: Fn(usize, usize) -> Result<Option<T>, AntError> + Sync,
+{
+	let next_job = AtomicUsize::new(0);
+	let thread_results = std::thread::scope(|scope| {
+		let handles = (0..thread_count.clamp(1, job_count.max(1))).map(|thread| {
+			let (next_job, stop, job) = (&next_job, &stop, &job);
+			return scope.spawn(move || {
+				let mut results = Vec::new();
+				loop {
+					let index = next_job.fetch_add(1, Ordering::Relaxed);
+					if index >= job_count || stop() {
+						return Ok(results);
+					}
+					match job(index, thread) {
+						Ok(Some(result)) => results.push((index, result)),
+						Ok(None) => {},
+						Err(error) => {
+							next_job.store(job_count, Ordering::Relaxed); // no point in starting more jobs
+							return Err(error);
+						},
+					}
+				}
+			});
+		}).collect::<Vec<_>>();
+		// a panic is a bug, not something to recover from
+		return handles.into_iter().map(|handle| handle.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic))).collect::<Vec<_>>();
+	});
+
+	let mut results = Vec::new();
+	for thread_result in thread_results {
+		results.extend(thread_result?);
+	}
+	results.sort_by_key(|(index, _)| *index);
+	return Ok(results.into_iter().map(|(_, result)| result).collect());
+}
+
+// Runs `solver` exactly `run_count` times from scratch, spread over `thread_count` threads with `run_jobs`.
+// Run `i` is seeded with `run_seed(solver.seed(), i)`, so the results don't depend on the number of threads.
+// An interrupt stops handing out runs, the ones cut short are kept if they got through an iteration
+pub fn run_batch(solver: &WorldState, run_count: usize, thread_count: usize) -> Result<BatchRunData, AntError> {
+	let start = std::time::Instant::now();
+	let runs = run_jobs(run_count, thread_count, || solver.is_interrupted(), |run, thread| {
+		let mut run_solver = solver.clone();
+		run_solver.reseed(run_seed(solver.seed, run));
+		run_solver.reset();
+		run_solver.do_all_iterations()?;
+		if run_solver.current_iteration == 0 {
+			return Ok(None);
+		}
+		return Ok(Some(RunRecord { run, thread, ..run_solver.run_record() }));
+	})?;
+	return Ok(BatchRunData { runs, wall_seconds: start.elapsed().as_secs_f64() });
+}
+
+fn average(values: &[f64]) -> f64 {
+	return if values.is_empty() { f64::NAN } else { values.iter().sum::<f64>() / values.len() as f64 };
+}
+
+// linear interpolation between the closest ranks, `sorted` has to be sorted
+fn quantile(sorted: &[f64], probability: f64) -> f64 {
+	if sorted.is_empty() {
+		return f64::NAN;
+	}
+	let position = probability * (sorted.len() - 1) as f64;
+	let lower = position.floor() as usize;
+	let upper = position.ceil() as usize;
+	return sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f64);
+}
+
+// 97.5th percentile of Student's t distribution, exact for small samples and Cornish-Fisher expansion above
+fn t_critical_975(degrees_of_freedom: usize) -> f64 {
+	const TABLE: [f64; 30] = [
+		12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228,
+		2.201, 2.179, 2.160, 2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086,
+		2.080, 2.074, 2.069, 2.064, 2.060, 2.056, 2.052, 2.048, 2.045, 2.042,
+	];
+	if degrees_of_freedom == 0 {
+		return f64::NAN;
+	}
+	if degrees_of_freedom <= TABLE.len() {
+		return TABLE[degrees_of_freedom - 1];
+	}
+	let z: f64 = 1.959964;
+	let df = degrees_of_freedom as f64;
+	return z + (z.powi(3) + z) / (4.0 * df) + (5.0 * z.powi(5) + 16.0 * z.powi(3) + 3.0 * z) / (96.0 * df.powi(2));
+}
+
+// ranks from 1 in ascending order, ties share the average of their ranks
+pub fn average_ranks(values: &[f64]) -> Vec<f64> {
+	let mut order = (0..values.len()).collect::<Vec<_>>();
+	order.sort_by(|first, second| values[*first].total_cmp(&values[*second]));
+	let mut ranks = vec![0.0; values.len()];
+	let mut start = 0;
+	while start < order.len() {
+		let mut end = start + 1;
+		while end < order.len() && values[order[end]] == values[order[start]] {
+			end += 1;
+		}
+		let rank = (start + end + 1) as f64 / 2.0;
+		for index in &order[start..end] {
+			ranks[*index] = rank;
+		}
+		start = end;
+	}
+	return ranks;
+}
+
+// Friedman test of `blocks`, each holding one result per treatment, results are ranked within their block
+pub struct FriedmanTest {
+	pub rank_sums: Vec<f64>,
+	pub statistic: f64, // chi-squared with one degree of freedom less than the treatments
+	pub p_value: f64, // NaN when every block is one big tie
+	rank_square_sum: f64,
+	block_count: usize,
+}
+
+impl FriedmanTest {
+	pub fn new(blocks: &[Vec<f64>]) -> Self {
+		let treatment_count = blocks.first().map_or(0, |block| block.len());
+		let mut rank_sums = vec![0.0; treatment_count];
+		let mut rank_square_sum = 0.0;
+		for block in blocks {
+			for (treatment, rank) in average_ranks(block).into_iter().enumerate() {
+				rank_sums[treatment] += rank;
+				rank_square_sum += rank * rank;
+			}
+		}
+		let (b, k) = (blocks.len() as f64, treatment_count as f64);
+		let correction = b * k * (k + 1.0).powi(2) / 4.0;
+		let statistic = (k - 1.0) * (rank_sums.iter().map(|sum| sum * sum).sum::<f64>() - b * correction) / (rank_square_sum - correction);
+		let p_value = if statistic.is_finite() { chi_squared_survival(statistic, k - 1.0) } else { f64::NAN };
+		return Self { rank_sums, statistic, p_value, rank_square_sum, block_count: blocks.len() };
+	}
+
+	// Conover's post-hoc test at the 5% level: whether treatments `first` and `second` differ
+	pub fn differ(&self, first: usize, second: usize) -> bool {
+		let (k, b) = (self.rank_sums.len(), self.block_count);
+		if k < 2 || b < 2 {
+			return false;
+		}
+		let rank_sum_squares = self.rank_sums.iter().map(|sum| sum * sum).sum::<f64>();
+		let spread = (2.0 * b as f64 * (self.rank_square_sum - rank_sum_squares / b as f64) / ((b - 1) * (k - 1)) as f64).sqrt();
+		let difference = (self.rank_sums[first] - self.rank_sums[second]).abs();
+		return difference > t_critical_975((b - 1) * (k - 1)) * spread;
+	}
+}
+
+// P(X >= x) for a chi-squared distribution
+pub fn chi_squared_survival(x: f64, degrees_of_freedom: f64) -> f64 {
+	if x <= 0.0 {
+		return 1.0;
+	}
+	return upper_regularized_gamma(degrees_of_freedom / 2.0, x / 2.0);
+}
+
+// P(X >= x) for an F distribution
+pub fn f_survival(x: f64, numerator_degrees: f64, denominator_degrees: f64) -> f64 {
+	if x <= 0.0 {
+		return 1.0;
+	}
+	return regularized_beta(denominator_degrees / (denominator_degrees + numerator_degrees * x), denominator_degrees / 2.0, numerator_degrees / 2.0);
+}
+
+// I_x(a, b) by its continued fraction, on the side where it converges quickly, as in Numerical Recipes
+fn regularized_beta(x: f64, a: f64, b: f64) -> f64 {
+	if x <= 0.0 {
+		return 0.0;
+	}
+	if x >= 1.0 {
+		return 1.0;
+	}
+	let log_prefactor = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln();
+	if x > (a + 1.0) / (a + b + 2.0) {
+		return 1.0 - regularized_beta(1.0 - x, b, a);
+	}
+	let tiny = 1e-300;
+	let mut c = 1.0;
+	let mut d = 1.0 - (a + b) * x / (a + 1.0);
+	d = if d.abs() < tiny { tiny } else { d };
+	d = 1.0 / d;
+	let mut fraction = d;
+	for step in 1..500 {
+		let m = step as f64;
+		// the even and the odd term of the fraction
+		for numerator in [m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m)), -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0))] {
+			d = 1.0 + numerator * d;
+			d = if d.abs() < tiny { tiny } else { d };
+			c = 1.0 + numerator / c;
+			c = if c.abs() < tiny { tiny } else { c };
+			d = 1.0 / d;
+			fraction *= d * c;
+		}
+		if (d * c - 1.0).abs() < 1e-15 {
+			break;
+		}
+	}
+	return (log_prefactor.exp() * fraction / a).clamp(0.0, 1.0);
+}
+
+// Q(a, x) by its series below a + 1 and by its continued fraction above, as in Numerical Recipes
+fn upper_regularized_gamma(a: f64, x: f64) -> f64 {
+	let log_prefactor = a * x.ln() - x - ln_gamma(a);
+	if x < a + 1.0 {
+		let (mut term, mut sum, mut denominator) = (1.0 / a, 1.0 / a, a);
+		for _ in 0..500 {
+			denominator += 1.0;
+			term *= x / denominator;
+			sum += term;
+			if term.abs() < sum.abs() * 1e-15 {
+				break;
+			}
+		}
+		return (1.0 - sum * log_prefactor.exp()).clamp(0.0, 1.0);
+	}
+	let tiny = 1e-300;
+	let mut b = x + 1.0 - a;
+	let mut c = 1.0 / tiny;
+	let mut d = 1.0 / b;
+	let mut fraction = d;
+	for step in 1..500 {
+		let an = -(step as f64) * (step as f64 - a);
+		b += 2.0;
+		d = an * d + b;
+		d = if d.abs() < tiny { tiny } else { d };
+		c = b + an / c;
+		c = if c.abs() < tiny { tiny } else { c };
+		d = 1.0 / d;
+		let delta = d * c;
+		fraction *= delta;
+		if (delta - 1.0).abs() < 1e-15 {
+			break;
+		}
+	}
+	return (fraction * log_prefactor.exp()).clamp(0.0, 1.0);
+}
+
+// Lanczos approximation with the Numerical Recipes coefficients, good to about 2e-10 relative error for positive arguments
+fn ln_gamma(x: f64) -> f64 {
+	const COEFFICIENTS: [f64; 6] = [76.18009172947146, -86.50532032941677, 24.01409824083091, -1.231739572450155, 0.1208650973866179e-2, -0.5395239384953e-5];
+	let tmp = x + 5.5 - (x + 0.5) * (x + 5.5).ln();
+	let series = COEFFICIENTS.iter().enumerate().fold(1.000000000190015, |sum, (index, coefficient)| sum + coefficient / (x + 1.0 + index as f64));
+	return -tmp + (2.5066282746310005 * series / x).ln();
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+		assert!((actual - expected).abs() <= tolerance, "expected {} within {}, got {}", expected, tolerance, actual);
+	}
+
+	fn batch(lengths: &[f64]) -> BatchRunData {
+		let runs = lengths.iter().enumerate().map(|(run, length)| RunRecord {
+			run,
+			thread: 0,
+			seed: run as u64,
+			length: *length,
+			best_iteration: 0,
+			iterations: 1,
+			runtime_seconds: 0.0,
+			mean_iteration_seconds: 0.0,
+			construction_seconds: 0.0,
+			best_update_seconds: 0.0,
+			pheromone_update_seconds: 0.0,
+		}).collect();
+		return BatchRunData { runs, wall_seconds: 0.0 };
+	}
+
+	#[test]
+	fn t_quantiles_match_the_tables() {
+		assert_close(t_critical_975(1), 12.7062, 1e-3);
+		assert_close(t_critical_975(10), 2.2281, 1e-3);
+		assert_close(t_critical_975(30), 2.0423, 1e-3);
+		// past the table
+		assert_close(t_critical_975(31), 2.0395, 1e-3);
+		assert_close(t_critical_975(40), 2.0211, 1e-3);
+		assert_close(t_critical_975(60), 2.0003, 1e-3);
+		assert_close(t_critical_975(120), 1.9799, 1e-3);
+		assert!(t_critical_975(0).is_nan());
+	}
+
+	#[test]
+	fn quantiles_interpolate_like_r() {
+		// R's default quantile type 7
+		let sorted = [1.0, 2.0, 4.0, 7.0];
+		assert_close(quantile(&sorted, 0.25), 1.75, 1e-12);
+		assert_close(quantile(&sorted, 0.5), 3.0, 1e-12);
+		assert_close(quantile(&sorted, 0.75), 4.75, 1e-12);
+		assert!(quantile(&[], 0.5).is_nan());
+	}
+
+	#[test]
+	fn summary_matches_t_test() {
+		// t.test(c(5, 3, 1, 4, 2)): mean 3, 95% interval 1.036757 to 4.963243
+		let summary = batch(&[5.0, 3.0, 1.0, 4.0, 2.0]).summary(Some(2.0));
+		assert_eq!(summary.run_count, 5);
+		assert_eq!((summary.min, summary.max), (1.0, 5.0));
+		assert_close(summary.mean, 3.0, 1e-12);
+		assert_close(summary.std_dev, 1.581139, 1e-6);
+		assert_close(summary.median, 3.0, 1e-12);
+		assert_close(summary.first_quartile, 2.0, 1e-12);
+		assert_close(summary.third_quartile, 4.0, 1e-12);
+		assert_close(summary.confidence_low, 1.036757, 1e-3);
+		assert_close(summary.confidence_high, 4.963243, 1e-3);
+		assert_eq!(summary.success_rate, Some(0.4));
+	}
+
+	#[test]
+	fn summary_of_one_run_has_no_spread() {
+		let summary = batch(&[7.0]).summary(None);
+		assert_eq!(summary.std_dev, 0.0);
+		assert!(summary.confidence_low.is_nan());
+		assert_eq!(summary.success_rate, None);
+		assert!(batch(&[]).summary(None).mean.is_nan());
+	}
+
+	#[test]
+	fn ln_gamma_matches_factorials() {
+		for (x, expected) in [(0.5, 0.5723649429247004), (0.1, 2.2527126517342055), (10.0, 12.801827480081467), (100.5, 361.4355404677776)] {
+			assert_close(ln_gamma(x), expected, 2e-10 * expected.abs());
+		}
+	}
+
+	#[test]
+	fn upper_gamma_on_both_sides_of_the_switch() {
+		for x in [0.5, 1.0, 3.0, 20.0] {
+			assert_close(upper_regularized_gamma(1.0, x), (-x).exp(), 1e-12);
+		}
+		// below a + 1 by the series, above it by the continued fraction
+		assert_close(upper_regularized_gamma(2.5, 1.0), 0.84914503608461, 1e-9);
+		assert_close(upper_regularized_gamma(2.5, 10.0), 0.00124973056303138, 1e-9);
+	}
+
+	#[test]
+	fn chi_squared_critical_values() {
+		assert_close(chi_squared_survival(3.841459, 1.0), 0.05, 1e-6);
+		assert_close(chi_squared_survival(5.991465, 2.0), 0.05, 1e-6);
+		assert_close(chi_squared_survival(18.307038, 10.0), 0.05, 1e-6);
+		assert_eq!(chi_squared_survival(0.0, 3.0), 1.0);
+	}
+
+	#[test]
+	fn ties_share_their_average_rank() {
+		assert_eq!(average_ranks(&[3.0, 1.0, 4.0, 1.0, 5.0]), vec![3.0, 1.5, 4.0, 1.5, 5.0]);
+		assert_eq!(average_ranks(&[2.0, 2.0, 2.0]), vec![2.0, 2.0, 2.0]);
+		assert!(average_ranks(&[]).is_empty());
+	}
+
+	// the rounding times of R's friedman.test example, one row per player and one column per method
+	const ROUNDING_TIMES: [[f64; 3]; 22] = [
+		[5.40, 5.50, 5.55], [5.85, 5.70, 5.75], [5.20, 5.60, 5.50], [5.55, 5.50, 5.40], [5.90, 5.85, 5.70], [5.45, 5.55, 5.60],
+		[5.40, 5.40, 5.35], [5.45, 5.50, 5.35], [5.25, 5.15, 5.00], [5.85, 5.80, 5.70], [5.25, 5.20, 5.10], [5.65, 5.55, 5.45],
+		[5.60, 5.35, 5.45], [5.05, 5.00, 4.95], [5.50, 5.50, 5.40], [5.45, 5.55, 5.50], [5.55, 5.55, 5.35], [5.45, 5.50, 5.55],
+		[5.50, 5.45, 5.25], [5.65, 5.60, 5.40], [5.70, 5.65, 5.55], [6.30, 6.30, 6.25],
+	];
+
+	#[test]
+	fn friedman_matches_r() {
+		// Friedman chi-squared = 11.143, df = 2, p-value = 0.003805
+		let test = FriedmanTest::new(&ROUNDING_TIMES.iter().map(|row| row.to_vec()).collect::<Vec<_>>());
+		assert_eq!(test.rank_sums, vec![53.0, 47.0, 32.0]);
+		assert_close(test.statistic, 11.142857, 1e-6);
+		assert_close(test.p_value, 0.003805, 1e-6);
+	}
+
+	#[test]
+	fn conover_separates_the_third_method() {
+		// the rank sums differ by 6, 21 and 15, the critical difference is t(0.975, 42) * 5.732 = 11.57
+		let test = FriedmanTest::new(&ROUNDING_TIMES.iter().map(|row| row.to_vec()).collect::<Vec<_>>());
+		assert!(!test.differ(0, 1));
+		assert!(test.differ(0, 2));
+		assert!(test.differ(1, 2));
+		assert!(!FriedmanTest::new(&[vec![1.0, 2.0]]).differ(0, 1));
+	}
+
+	#[test]
+	fn friedman_of_one_big_tie_has_no_p_value() {
+		assert!(FriedmanTest::new(&[vec![1.0, 1.0], vec![2.0, 2.0]]).p_value.is_nan());
+	}
+
+	#[test]
+	fn regularized_beta_matches_closed_forms() {
+		for x in [0.1, 0.5, 0.9] {
+			assert_close(regularized_beta(x, 1.0, 1.0), x, 1e-12);
+		}
+		assert_close(regularized_beta(0.5, 3.5, 3.5), 0.5, 1e-12);
+		// 6x^2 - 8x^3 + 3x^4 for a = 2, b = 3
+		assert_close(regularized_beta(0.3, 2.0, 3.0), 0.3483, 1e-12);
+		assert_close(regularized_beta(0.1, 0.5, 0.5), 0.204832764699133, 1e-9);
+		assert_close(regularized_beta(0.4, 30.0, 40.0), 0.318143802590563, 1e-9);
+		assert_eq!((regularized_beta(0.0, 2.0, 3.0), regularized_beta(1.0, 2.0, 3.0)), (0.0, 1.0));
+	}
+
+	#[test]
+	fn f_critical_values() {
+		assert_close(f_survival(4.256495, 2.0, 9.0), 0.05, 1e-6);
+		assert_close(f_survival(4.964603, 1.0, 10.0), 0.05, 1e-6);
+		assert_close(f_survival(2.710890, 5.0, 20.0), 0.05, 1e-6);
+		assert_eq!(f_survival(0.0, 2.0, 9.0), 1.0);
+	}
+}
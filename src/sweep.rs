@@ -0,0 +1,31 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::stats::run_jobs;
+use crate::{run_seed, AntError, ConfigData, ExperimentRecord, GraphNode, RunRecord, WorldState};
+
+// Runs every parameter set `run_count` times on every instance, all runs share one pool of `thread_count` threads.
+// Run `i` is seeded with `run_seed(seed, i)` for every set and instance, so sets are compared on the same random numbers.
+// Sets without a seed share one random one. Records come back ordered by set, instance and run
+pub fn run_sweep(instances: &[(String, Vec<GraphNode>)], sets: &[ConfigData], run_count: usize, thread_count: usize, interrupt_flag: Option<Arc<AtomicBool>>) -> Result<Vec<ExperimentRecord>, AntError> {
+	for set in sets {
+		set.validate()?;
+	}
+	let shared_seed = rand::random();
+	let is_interrupted = || interrupt_flag.as_ref().is_some_and(|flag| flag.load(Ordering::Relaxed));
+	let job_count = sets.len() * instances.len() * run_count;
+	return run_jobs(job_count, thread_count, is_interrupted, |job, thread| {
+		let (set_index, instance_index, run) = (job / (instances.len() * run_count), job / run_count % instances.len(), job % run_count);
+		let (instance, nodes) = &instances[instance_index];
+		let set = &sets[set_index];
+		let config = ConfigData { seed: Some(run_seed(set.seed.unwrap_or(shared_seed), run)), ..set.clone() };
+		let mut solver = WorldState::new(nodes.clone(), config)?;
+		if let Some(flag) = &interrupt_flag {
+			solver.set_interrupt_flag(flag.clone());
+		}
+		solver.do_all_iterations()?;
+		if solver.current_iteration == 0 {
+			return Ok(None);
+		}
+		return Ok(Some(ExperimentRecord::new(set_index, instance, set, &RunRecord { run, thread, ..solver.run_record() })));
+	});
+}
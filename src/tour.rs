@@ -0,0 +1,177 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::{AntError, GraphNode, NodeId};
+
+// What a tour is measured as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Objective {
+	OpenPath, // from the first node to the last, what the colony optimises
+	ClosedTour, // back to the first node at the end
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TourFormat {
+	Tsplib, // a TSPLIB .tour file
+	Csv, // with a header that has an `id` column, like `WorldState::solution_to_csv` writes
+	Json, // an array of ids or nodes, or an object with such a `tour` like `RunResult`
+	List, // ids separated by whitespace
+}
+
+impl TourFormat {
+	// by extension, anything unknown is read as a plain list
+	pub fn from_path(path: &Path) -> Self {
+		return match path.extension().and_then(|extension| extension.to_str()) {
+			Some("tour") => TourFormat::Tsplib,
+			Some("csv") => TourFormat::Csv,
+			Some("json") => TourFormat::Json,
+			_ => TourFormat::List,
+		};
+	}
+}
+
+// TSPLIB numbers nodes from 1, instances that don't do the same are numbered by their line instead
+pub(crate) fn uses_tsplib_ids(nodes: &[GraphNode]) -> bool {
+	return nodes.iter().all(|node| matches!(node.id, NodeId::Number(number) if number >= 1));
+}
+
+// `nodes` is the instance the tour belongs to, TSPLIB tours may refer to its lines instead of its ids
+pub fn read_tour<R: Read>(mut reader: R, format: TourFormat, nodes: &[GraphNode]) -> Result<Vec<NodeId>, AntError> {
+	let mut text = String::new();
+	reader.read_to_string(&mut text)?;
+	return match format {
+		TourFormat::Tsplib => read_tsplib_tour(&text, nodes),
+		TourFormat::Csv => read_csv_tour(&text),
+		TourFormat::Json => read_json_tour(&text),
+		TourFormat::List => Ok(text.split_whitespace().map(NodeId::from).collect()),
+	};
+}
+
+pub fn read_tour_file(path: &Path, nodes: &[GraphNode]) -> Result<Vec<NodeId>, AntError> {
+	return read_tour(std::fs::File::open(path)?, TourFormat::from_path(path), nodes);
+}
+
+fn read_tsplib_tour(text: &str, nodes: &[GraphNode]) -> Result<Vec<NodeId>, AntError> {
+	let by_line = !uses_tsplib_ids(nodes);
+	let mut tour = Vec::new();
+	let mut in_tour_section = false;
+	for (line_index, line) in text.lines().enumerate() {
+		let line = line.trim();
+		if !in_tour_section {
+			in_tour_section = line == "TOUR_SECTION";
+			continue;
+		}
+		if line == "EOF" {
+			break;
+		}
+		for value in line.split_whitespace() {
+			let number: i64 = value.parse().map_err(|_| AntError::Parse { line: line_index + 1, message: format!("{} is not a node number", value) })?;
+			if number == -1 {
+				return Ok(tour);
+			}
+			// numbers past the last line are kept as they are, so they show up as unknown
+			let line_node = usize::try_from(number - 1).ok().and_then(|index| nodes.get(index));
+			tour.push(match line_node {
+				Some(node) if by_line => node.id.clone(),
+				_ => NodeId::Number(number),
+			});
+		}
+	}
+	if !in_tour_section {
+		return Err(AntError::Parse { line: text.lines().count(), message: "there is no TOUR_SECTION".to_string() });
+	}
+	return Ok(tour);
+}
+
+fn read_csv_tour(text: &str) -> Result<Vec<NodeId>, AntError> {
+	let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(text.as_bytes());
+	let to_parse_error = |error: csv::Error| AntError::Parse { line: error.position().map_or(0, |position| position.line() as usize), message: error.to_string() };
+	let id_column = reader.headers().map_err(to_parse_error)?.iter().position(|header| header == "id")
+		.ok_or_else(|| AntError::Parse { line: 1, message: "the header has no id column".to_string() })?;
+	let mut tour = Vec::new();
+	for record in reader.records() {
+		let record = record.map_err(to_parse_error)?;
+		let line = record.position().map_or(0, |position| position.line() as usize);
+		let id = record.get(id_column).ok_or_else(|| AntError::Parse { line, message: "the id column is missing".to_string() })?;
+		tour.push(NodeId::from(id));
+	}
+	return Ok(tour);
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonTourEntry {
+	Id(NodeId),
+	Node { id: NodeId },
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonTour {
+	List(Vec<JsonTourEntry>),
+	Result { tour: Vec<JsonTourEntry> },
+}
+
+fn read_json_tour(text: &str) -> Result<Vec<NodeId>, AntError> {
+	let tour: JsonTour = serde_json::from_str(text).map_err(|error| AntError::Parse { line: error.line(), message: "expected an array of node ids or nodes, or an object with a tour".to_string() })?;
+	let entries = match tour {
+		JsonTour::List(entries) => entries,
+		JsonTour::Result { tour } => tour,
+	};
+	return Ok(entries.into_iter().map(|entry| match entry {
+		JsonTourEntry::Id(id) => id,
+		JsonTourEntry::Node { id } => id,
+	}).collect());
+}
+
+// How a tour measures up against an instance, see `evaluate_tour`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TourEvaluation {
+	pub objective: Objective,
+	pub length: f64, // over the known nodes in the given order, duplicates included
+	pub node_count: usize, // of the instance
+	pub visited: usize, // distinct known nodes in the tour
+	pub duplicates: Vec<NodeId>, // each id visited more than once, listed once
+	pub missing: Vec<NodeId>, // in instance order
+	pub unknown: Vec<NodeId>, // ids that aren't in the instance
+}
+
+impl TourEvaluation {
+	// every node visited exactly once and nothing else
+	pub fn is_valid(&self) -> bool {
+		return self.duplicates.is_empty() && self.missing.is_empty() && self.unknown.is_empty();
+	}
+}
+
+pub fn evaluate_tour(nodes: &[GraphNode], tour: &[NodeId], objective: Objective) -> TourEvaluation {
+	let by_id = nodes.iter().map(|node| (&node.id, node)).collect::<HashMap<_, _>>();
+	let mut seen = HashSet::new();
+	let mut duplicates = Vec::new();
+	let mut unknown = Vec::new();
+	let mut path = Vec::with_capacity(tour.len());
+	for id in tour {
+		match by_id.get(id) {
+			Some(node) => {
+				if !seen.insert(id) && !duplicates.contains(id) {
+					duplicates.push(id.clone());
+				}
+				path.push(*node);
+			},
+			None => unknown.push(id.clone()),
+		}
+	}
+	let mut length = path.windows(2).map(|pair| pair[0].distance_to(pair[1])).sum::<f64>();
+	if let (Objective::ClosedTour, Some(first), Some(last)) = (objective, path.first(), path.last()) {
+		length += last.distance_to(first);
+	}
+	return TourEvaluation {
+		objective,
+		length,
+		node_count: nodes.len(),
+		visited: seen.len(),
+		duplicates,
+		missing: nodes.iter().filter(|node| !seen.contains(&node.id)).map(|node| node.id.clone()).collect(),
+		unknown,
+	};
+}
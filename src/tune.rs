@@ -0,0 +1,247 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use rand::prelude::*;
+use rand_xoshiro::Xoshiro256PlusPlus;
+use serde::{Deserialize, Serialize};
+use crate::stats::{run_jobs, FriedmanTest};
+use crate::AntError;
+
+// Values a tuned parameter can take, ranges include both ends
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ParameterDomain {
+	Integer { min: i64, max: i64 },
+	Real { min: f64, max: f64 },
+	Categorical(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TuningParameter {
+	pub name: String,
+	pub domain: ParameterDomain,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ParameterValue {
+	Integer(i64),
+	Real(f64),
+	Category(String),
+}
+
+impl std::fmt::Display for ParameterValue {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		return match self {
+			ParameterValue::Integer(value) => write!(f, "{}", value),
+			ParameterValue::Real(value) => write!(f, "{}", value),
+			ParameterValue::Category(value) => write!(f, "{}", value),
+		};
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct RaceSettings {
+	pub max_experiments: usize, // runs over the whole tuning
+	pub first_test: usize, // instances every configuration runs on before the first elimination
+	pub seed: u64,
+	pub thread_count: usize,
+	pub interrupt_flag: Option<Arc<AtomicBool>>, // stops the tuning after the current step, the results so far are kept
+}
+
+impl Default for RaceSettings {
+	fn default() -> Self {
+		return Self {
+			max_experiments: 1000,
+			first_test: 5,
+			seed: 0,
+			thread_count: 1,
+			interrupt_flag: None,
+		};
+	}
+}
+
+// A configuration that survived the last race
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TunedConfiguration {
+	pub values: Vec<ParameterValue>, // in the order of the tuned parameters
+	pub runs: usize,
+	pub mean_length: f64, // over every run of the configuration
+	pub mean_rank: f64, // among the survivors of the last race, over the instances they all ran on
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RaceResult {
+	pub elites: Vec<TunedConfiguration>, // best first
+	pub experiments: usize,
+	pub iterations: usize,
+}
+
+struct Candidate {
+	values: Vec<ParameterValue>,
+	results: Vec<Option<f64>>, // by race step, shared between iterations so elites don't run again
+}
+
+// Iterated F-race: every iteration samples configurations around the elites of the last one and races them.
+// A race runs every configuration still in it on one more (instance, seed) pair per step, after `first_test` steps
+// the Friedman test decides whether the worst ones get dropped. The race ends with few survivors or when its share of
+// the budget is used up, the survivors become the next elites.
+// `evaluate` runs one configuration on an instance with a seed and returns the found length
+pub fn iterated_race<F>(parameters: &[TuningParameter], instance_count: usize, settings: &RaceSettings, evaluate: F) -> Result<RaceResult, AntError>
+where
+	F: Fn(&[ParameterValue], usize, u64) -> Result<f64, AntError> + Sync,
+{
+	if instance_count == 0 {
+		return Err(AntError::EmptyInstance);
+	}
+	let mut random_source = Xoshiro256PlusPlus::seed_from_u64(settings.seed);
+	let is_interrupted = || settings.interrupt_flag.as_ref().is_some_and(|flag| flag.load(Ordering::Relaxed));
+	let dimensions = parameters.len().max(1);
+	let iteration_count = 2 + dimensions.ilog2() as usize;
+	let survivor_count = 2 + dimensions.ilog2() as usize;
+
+	// every pass over the instances comes in a new order with new seeds
+	let mut tasks: Vec<(usize, u64)> = Vec::new();
+	let mut elites: Vec<Candidate> = Vec::new();
+	let mut experiments = 0;
+	let mut iterations = 0;
+	for iteration in 1..=iteration_count {
+		let budget = (settings.max_experiments - experiments) / (iteration_count - iteration + 1);
+		let configuration_count = budget / (settings.first_test + iteration.min(5));
+		if configuration_count <= elites.len() || is_interrupted() {
+			break;
+		}
+		iterations = iteration;
+		let new_count = configuration_count - elites.len();
+		let mut candidates = std::mem::take(&mut elites);
+		let elite_count = candidates.len();
+		for _ in 0..new_count {
+			let values = match elite_count {
+				0 => sample_uniform(parameters, &mut random_source),
+				_ => {
+					// better elites are picked as parents more often
+					let weights = (0..elite_count).map(|rank| elite_count - rank).collect::<Vec<_>>();
+					let parent = rand::distributions::WeightedIndex::new(&weights).unwrap().sample(&mut random_source);
+					sample_around(parameters, &candidates[parent].values, iteration, iteration_count, configuration_count, &mut random_source)
+				},
+			};
+			candidates.push(Candidate { values, results: Vec::new() });
+		}
+
+		let mut alive = (0..candidates.len()).collect::<Vec<_>>();
+		let mut step = 0;
+		let mut used = 0;
+		loop {
+			while tasks.len() <= step {
+				let mut order = (0..instance_count).collect::<Vec<_>>();
+				order.shuffle(&mut random_source);
+				tasks.extend(order.into_iter().map(|instance| (instance, random_source.gen())));
+			}
+			let pending = alive.iter().copied().filter(|candidate| candidates[*candidate].results.get(step).copied().flatten().is_none()).collect::<Vec<_>>();
+			if used + pending.len() > budget || is_interrupted() {
+				break;
+			}
+			let (instance, seed) = tasks[step];
+			let lengths = run_jobs(pending.len(), settings.thread_count, is_interrupted, |job, _| {
+				return evaluate(&candidates[pending[job]].values, instance, seed).map(Some);
+			})?;
+			if lengths.len() < pending.len() || is_interrupted() {
+				break; // runs cut short can't be compared with the others
+			}
+			for (candidate, length) in pending.iter().zip(lengths) {
+				let results = &mut candidates[*candidate].results;
+				results.resize(results.len().max(step + 1), None);
+				results[step] = Some(length);
+			}
+			used += pending.len();
+			step += 1;
+			if step >= settings.first_test && alive.len() > survivor_count {
+				let test = FriedmanTest::new(&blocks(&candidates, &alive, step));
+				if test.p_value < 0.05 {
+					let best = (0..alive.len()).min_by(|first, second| test.rank_sums[*first].total_cmp(&test.rank_sums[*second])).unwrap();
+					alive = alive.iter().enumerate().filter(|(index, _)| !test.differ(best, *index)).map(|(_, candidate)| *candidate).collect();
+				}
+			}
+			if alive.len() <= survivor_count && step >= settings.first_test {
+				break;
+			}
+		}
+		experiments += used;
+		if step == 0 {
+			// nothing ran, keep the elites as they were
+			elites = candidates.into_iter().take(elite_count).collect();
+			break;
+		}
+
+		// the survivors are ranked over the steps they all completed
+		let test = FriedmanTest::new(&blocks(&candidates, &alive, step));
+		let mut ranked = alive.iter().copied().zip(test.rank_sums).collect::<Vec<_>>();
+		ranked.sort_by(|first, second| first.1.total_cmp(&second.1));
+		let mut candidates = candidates.into_iter().map(Some).collect::<Vec<_>>();
+		elites = ranked.into_iter().take(survivor_count).filter_map(|(candidate, _)| candidates[candidate].take()).collect();
+	}
+
+	let step = elites.iter().map(|elite| elite.results.iter().take_while(|result| result.is_some()).count()).min().unwrap_or(0);
+	let test = FriedmanTest::new(&blocks(&elites, &(0..elites.len()).collect::<Vec<_>>(), step));
+	let mut tuned = elites.into_iter().zip(test.rank_sums).map(|(elite, rank_sum)| {
+		let lengths = elite.results.iter().flatten().copied().collect::<Vec<_>>();
+		return TunedConfiguration {
+			values: elite.values,
+			runs: lengths.len(),
+			mean_length: lengths.iter().sum::<f64>() / lengths.len() as f64,
+			mean_rank: rank_sum / step as f64,
+		};
+	}).collect::<Vec<_>>();
+	tuned.sort_by(|first, second| first.mean_rank.total_cmp(&second.mean_rank).then(first.mean_length.total_cmp(&second.mean_length)));
+	return Ok(RaceResult { elites: tuned, experiments, iterations });
+}
+
+// one block per step with the result of every candidate in `alive`
+fn blocks(candidates: &[Candidate], alive: &[usize], step_count: usize) -> Vec<Vec<f64>> {
+	return (0..step_count).map(|step| alive.iter().map(|candidate| candidates[*candidate].results[step].unwrap()).collect()).collect();
+}
+
+pub(crate) fn sample_uniform(parameters: &[TuningParameter], random_source: &mut Xoshiro256PlusPlus) -> Vec<ParameterValue> {
+	return parameters.iter().map(|parameter| match &parameter.domain {
+		ParameterDomain::Integer { min, max } => ParameterValue::Integer(random_source.gen_range(*min..=*max)),
+		ParameterDomain::Real { min, max } => ParameterValue::Real(round_real(random_source.gen_range(*min..=*max)).clamp(*min, *max)),
+		ParameterDomain::Categorical(values) => ParameterValue::Category(values.choose(random_source).unwrap().clone()),
+	}).collect();
+}
+
+// Numbers come from a normal distribution around the parent that narrows every iteration, categories keep the
+// parent's value more and more often
+fn sample_around(parameters: &[TuningParameter], parent: &[ParameterValue], iteration: usize, iteration_count: usize, configuration_count: usize, random_source: &mut Xoshiro256PlusPlus) -> Vec<ParameterValue> {
+	let shrink = (1.0 / configuration_count as f64).powf((iteration - 1) as f64 / parameters.len().max(1) as f64);
+	return parameters.iter().zip(parent).map(|(parameter, parent)| match (&parameter.domain, parent) {
+		(ParameterDomain::Integer { min, max }, ParameterValue::Integer(value)) => {
+			let sampled = sample_truncated_normal(*value as f64 + 0.5, (max - min + 1) as f64 / 2.0 * shrink, *min as f64, (*max + 1) as f64, random_source);
+			ParameterValue::Integer((sampled.floor() as i64).clamp(*min, *max))
+		},
+		(ParameterDomain::Real { min, max }, ParameterValue::Real(value)) => {
+			ParameterValue::Real(round_real(sample_truncated_normal(*value, (max - min) / 2.0 * shrink, *min, *max, random_source)).clamp(*min, *max))
+		},
+		(ParameterDomain::Categorical(values), ParameterValue::Category(value)) => {
+			let keep_chance = iteration as f64 / (iteration_count + 1) as f64;
+			ParameterValue::Category(if random_source.gen_bool(keep_chance) { value.clone() } else { values.choose(random_source).unwrap().clone() })
+		},
+		_ => unreachable!("parameter values always match their domain"),
+	}).collect();
+}
+
+// resamples values outside [min, max] a few times before clamping them
+fn sample_truncated_normal(mean: f64, std_dev: f64, min: f64, max: f64, random_source: &mut Xoshiro256PlusPlus) -> f64 {
+	let mut value = mean;
+	for _ in 0..16 {
+		// Box-Muller transform
+		let (uniform, angle) = (1.0 - random_source.gen::<f64>(), random_source.gen::<f64>());
+		value = mean + std_dev * (-2.0 * uniform.ln()).sqrt() * (2.0 * std::f64::consts::PI * angle).cos();
+		if (min..=max).contains(&value) {
+			return value;
+		}
+	}
+	return value.clamp(min, max);
+}
+
+// four decimals are plenty for the algorithm and keep the configurations readable
+fn round_real(value: f64) -> f64 {
+	return (value * 1e4).round() / 1e4;
+}
@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+
+use ant_colony::stats::FriedmanTest;
+use ant_colony::{BatchRunData, ExperimentRecord};
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::significance::{adjust_p_values, mann_whitney_u, wilcoxon_signed_rank, Correction, RankTest};
+
+// Parameter sets are numbered from 0 in the order they first appear, for a single sweep file these are its set numbers
+pub struct Dataset<'a> {
+	pub configurations: Vec<&'a ExperimentRecord>, // the first run of every parameter set, for its parameters
+	pub instances: Vec<&'a str>,
+	runs: HashMap<(usize, usize), Vec<&'a ExperimentRecord>>, // by parameter set and instance
+}
+
+impl<'a> Dataset<'a> {
+	pub fn new(records: &'a [ExperimentRecord]) -> Self {
+		let mut configurations = Vec::new();
+		let mut instances = Vec::new();
+		let mut configuration_indices = HashMap::new();
+		let mut instance_indices = HashMap::new();
+		let mut runs: HashMap<(usize, usize), Vec<&ExperimentRecord>> = HashMap::new();
+		for record in records {
+			let configuration = *configuration_indices.entry(parameter_key(record)).or_insert_with(|| {
+				configurations.push(record);
+				return configurations.len() - 1;
+			});
+			let instance = *instance_indices.entry(record.instance.as_str()).or_insert_with(|| {
+				instances.push(record.instance.as_str());
+				return instances.len() - 1;
+			});
+			runs.entry((configuration, instance)).or_default().push(record);
+		}
+		return Self { configurations, instances, runs };
+	}
+
+	pub fn runs(&self, configuration: usize, instance: usize) -> &[&'a ExperimentRecord] {
+		return self.runs.get(&(configuration, instance)).map_or(&[], |runs| runs.as_slice());
+	}
+
+	pub fn lengths(&self, configuration: usize, instance: usize) -> Vec<f64> {
+		return self.runs(configuration, instance).iter().map(|record| record.length).collect();
+	}
+
+	pub fn mean_length(&self, configuration: usize, instance: usize) -> f64 {
+		let lengths = self.lengths(configuration, instance);
+		return lengths.iter().sum::<f64>() / lengths.len() as f64;
+	}
+
+	// parameter sets with runs on the instance
+	pub fn configurations_on(&self, instance: usize) -> Vec<usize> {
+		return (0..self.configurations.len()).filter(|configuration| !self.runs(*configuration, instance).is_empty()).collect();
+	}
+
+	// parameter sets with runs on every instance
+	pub fn complete_configurations(&self) -> Vec<usize> {
+		return (0..self.configurations.len()).filter(|configuration| (0..self.instances.len()).all(|instance| !self.runs(*configuration, instance).is_empty())).collect();
+	}
+
+	pub fn describe(&self, configuration: usize) -> String {
+		return describe(self.configurations[configuration]);
+	}
+}
+
+// everything but the instance and the run itself, floats by their bits so equal parameters always group together
+pub fn parameter_key(record: &ExperimentRecord) -> String {
+	return format!("{}|{}|{:?}|{:?}|{:?}|{:?}|{}|{}|{}|{}", record.variant, record.ant_count, record.max_iterations,
+		record.time_limit.map(f64::to_bits), record.target_length.map(f64::to_bits), record.stagnation_limit,
+		record.pheromone_evaporation_coefficient.to_bits(), record.random_choice_chance.to_bits(), record.pheromone_weight.to_bits(), record.heuristic_weight.to_bits());
+}
+
+// the parameters of a set in the summary's column names, stopping options only when they're set
+pub fn describe(record: &ExperimentRecord) -> String {
+	let mut description = format!("{} ants={}", record.variant, record.ant_count);
+	if let Some(iterations) = record.max_iterations {
+		description.push_str(&format!(" iterations={}", iterations));
+	}
+	if let Some(time_limit) = record.time_limit {
+		description.push_str(&format!(" time_limit={}", time_limit));
+	}
+	if let Some(target_length) = record.target_length {
+		description.push_str(&format!(" target_length={}", target_length));
+	}
+	if let Some(stagnation_limit) = record.stagnation_limit {
+		description.push_str(&format!(" stagnation_limit={}", stagnation_limit));
+	}
+	description.push_str(&format!(" evap_coeff={} rand_chance={} pher_weight={} heur_weight={}", record.pheromone_evaporation_coefficient,
+		record.random_choice_chance, record.pheromone_weight, record.heuristic_weight));
+	return description;
+}
+
+#[derive(Serialize)]
+pub struct RankRow<'a> {
+	instance: &'a str,
+	rank: f64, // 1 is the shortest mean length, ties share the average rank
+	configuration: usize,
+	parameters: String,
+	runs: usize,
+	mean: f64,
+	median: f64,
+	std_dev: f64,
+}
+
+// every parameter set ranked by its mean length on every instance, best first
+pub fn rank<'a>(dataset: &Dataset<'a>) -> Vec<RankRow<'a>> {
+	let mut rows = Vec::new();
+	for (instance, name) in dataset.instances.iter().enumerate() {
+		let configurations = dataset.configurations_on(instance);
+		let means = configurations.iter().map(|configuration| dataset.mean_length(*configuration, instance)).collect::<Vec<_>>();
+		let ranks = ant_colony::stats::average_ranks(&means);
+		let mut instance_rows = configurations.iter().zip(ranks).map(|(configuration, rank)| {
+			let runs = BatchRunData { runs: dataset.runs(*configuration, instance).iter().map(|record| record.run_record()).collect(), ..BatchRunData::default() };
+			let summary = runs.summary(None);
+			return RankRow {
+				instance: name,
+				rank,
+				configuration: *configuration,
+				parameters: dataset.describe(*configuration),
+				runs: summary.run_count,
+				mean: summary.mean,
+				median: summary.median,
+				std_dev: summary.std_dev,
+			};
+		}).collect::<Vec<_>>();
+		instance_rows.sort_by(|first, second| first.rank.total_cmp(&second.rank).then(first.configuration.cmp(&second.configuration)));
+		rows.extend(instance_rows);
+	}
+	return rows;
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+pub enum PairedTest {
+	Wilcoxon, // signed-rank over runs with the same seed, the runs of a sweep share their seeds between sets
+	MannWhitney, // rank sum over all runs, for results that weren't run with common seeds
+}
+
+#[derive(Serialize)]
+pub struct ComparisonRow<'a> {
+	instance: &'a str,
+	first: usize,
+	second: usize,
+	first_mean: f64,
+	second_mean: f64,
+	first_shorter_probability: f64, // chance that a run of the first set is shorter than one of the second, ties count half
+	paired_runs: usize,
+	wilcoxon_statistic: Option<f64>,
+	wilcoxon_p: Option<f64>,
+	wilcoxon_adjusted_p: Option<f64>,
+	mann_whitney_u: f64,
+	mann_whitney_p: f64,
+	mann_whitney_adjusted_p: f64,
+	better: Option<usize>, // the set with the shorter lengths when the chosen test's adjusted p-value is below alpha
+}
+
+// Both tests for every pair of parameter sets on every instance. The p-values are corrected per instance and test,
+// over the pairs on that instance
+pub fn compare<'a>(dataset: &Dataset<'a>, test: PairedTest, correction: Correction, alpha: f64) -> Vec<ComparisonRow<'a>> {
+	let mut rows = Vec::new();
+	for (instance, name) in dataset.instances.iter().enumerate() {
+		let configurations = dataset.configurations_on(instance);
+		let mut results: Vec<(usize, usize, usize, Option<RankTest>, RankTest)> = Vec::new();
+		for (position, first) in configurations.iter().enumerate() {
+			for second in &configurations[position + 1..] {
+				let pairs = paired_lengths(dataset.runs(*first, instance), dataset.runs(*second, instance));
+				let wilcoxon = (!pairs.is_empty()).then(|| wilcoxon_signed_rank(&pairs));
+				let mann_whitney = mann_whitney_u(&dataset.lengths(*first, instance), &dataset.lengths(*second, instance));
+				results.push((*first, *second, pairs.len(), wilcoxon, mann_whitney));
+			}
+		}
+		let wilcoxon_adjusted = adjust_p_values(&results.iter().map(|result| result.3.as_ref().map(|test| test.p_value)).collect::<Vec<_>>(), correction);
+		let mann_whitney_adjusted = adjust_p_values(&results.iter().map(|result| Some(result.4.p_value)).collect::<Vec<_>>(), correction);
+		for (index, (first, second, paired_runs, wilcoxon, mann_whitney)) in results.into_iter().enumerate() {
+			let pair_count = (dataset.runs(first, instance).len() * dataset.runs(second, instance).len()) as f64;
+			let (chosen, adjusted_p) = match test {
+				PairedTest::Wilcoxon => (wilcoxon.as_ref(), wilcoxon_adjusted[index]),
+				PairedTest::MannWhitney => (Some(&mann_whitney), mann_whitney_adjusted[index]),
+			};
+			let better = chosen.zip(adjusted_p).filter(|(_, p_value)| *p_value < alpha).map(|(test, _)| if test.first_lower { first } else { second });
+			rows.push(ComparisonRow {
+				instance: name,
+				first,
+				second,
+				first_mean: dataset.mean_length(first, instance),
+				second_mean: dataset.mean_length(second, instance),
+				first_shorter_probability: 1.0 - mann_whitney.statistic / pair_count,
+				paired_runs,
+				wilcoxon_statistic: wilcoxon.as_ref().map(|test| test.statistic),
+				wilcoxon_p: wilcoxon.as_ref().map(|test| test.p_value),
+				wilcoxon_adjusted_p: wilcoxon_adjusted[index],
+				mann_whitney_u: mann_whitney.statistic,
+				mann_whitney_p: mann_whitney.p_value,
+				mann_whitney_adjusted_p: mann_whitney_adjusted[index].unwrap(),
+				better,
+			});
+		}
+	}
+	return rows;
+}
+
+// runs of the two sets with the same seed, repeated seeds are paired in the order they appear
+fn paired_lengths(first: &[&ExperimentRecord], second: &[&ExperimentRecord]) -> Vec<(f64, f64)> {
+	let mut second_by_seed = lengths_by_seed(second);
+	return first.iter().filter_map(|record| second_by_seed.get_mut(&record.seed).and_then(|lengths| lengths.pop()).map(|length| (record.length, length))).collect();
+}
+
+// lengths of the runs with every seed, the first run is popped first so every run is paired at most once
+fn lengths_by_seed(runs: &[&ExperimentRecord]) -> HashMap<u64, Vec<f64>> {
+	let mut by_seed: HashMap<u64, Vec<f64>> = HashMap::new();
+	for record in runs.iter().rev() {
+		by_seed.entry(record.seed).or_default().push(record.length);
+	}
+	return by_seed;
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+pub enum BlockKind {
+	Instances, // the mean length of every set on an instance
+	Runs, // the runs with the same instance and seed, for sweeps over a single instance
+}
+
+#[derive(Serialize)]
+pub struct FriedmanRow {
+	configuration: usize,
+	parameters: String,
+	mean_rank: f64,
+	mean_length: f64, // over the blocks
+	differs_from_best: bool, // by Conover's post-hoc test at the 5% level
+}
+
+pub struct FriedmanAnalysis {
+	pub rows: Vec<FriedmanRow>, // by mean rank, best first
+	pub test: FriedmanTest,
+	pub block_count: usize,
+	pub excluded: Vec<usize>, // sets missing from some instance
+}
+
+impl FriedmanAnalysis {
+	pub fn best(&self) -> usize {
+		return self.rows[0].configuration;
+	}
+
+	pub fn equivalent_to_best(&self) -> Vec<usize> {
+		return self.rows[1..].iter().filter(|row| !row.differs_from_best).map(|row| row.configuration).collect();
+	}
+}
+
+// Friedman test over the parameter sets that ran on every instance
+pub fn friedman(dataset: &Dataset, block_kind: BlockKind) -> Result<FriedmanAnalysis, String> {
+	let configurations = dataset.complete_configurations();
+	let excluded = (0..dataset.configurations.len()).filter(|configuration| !configurations.contains(configuration)).collect::<Vec<_>>();
+	if configurations.len() < 2 {
+		return Err(format!("The Friedman test needs at least two parameter sets that ran on every instance, found {}", configurations.len()));
+	}
+	let blocks = match block_kind {
+		BlockKind::Instances => (0..dataset.instances.len())
+			.map(|instance| configurations.iter().map(|configuration| dataset.mean_length(*configuration, instance)).collect::<Vec<_>>())
+			.collect::<Vec<_>>(),
+		BlockKind::Runs => {
+			let mut blocks = Vec::new();
+			for instance in 0..dataset.instances.len() {
+				// the seeds of the first set, kept where every other set has an unused run with the same seed
+				let mut by_seed = configurations.iter().map(|configuration| lengths_by_seed(dataset.runs(*configuration, instance))).collect::<Vec<_>>();
+				for record in dataset.runs(configurations[0], instance) {
+					if by_seed.iter().all(|lengths| lengths.get(&record.seed).is_some_and(|lengths| !lengths.is_empty())) {
+						blocks.push(by_seed.iter_mut().map(|lengths| lengths.get_mut(&record.seed).unwrap().pop().unwrap()).collect::<Vec<_>>());
+					}
+				}
+			}
+			blocks
+		},
+	};
+	if blocks.len() < 2 {
+		return Err(match block_kind {
+			BlockKind::Instances => "The Friedman test needs at least two instances, use `--blocks runs` for a single instance".to_string(),
+			BlockKind::Runs => "The Friedman test needs at least two runs with the same seed in every parameter set".to_string(),
+		});
+	}
+	let test = FriedmanTest::new(&blocks);
+	let mean_lengths = (0..configurations.len()).map(|index| blocks.iter().map(|block| block[index]).sum::<f64>() / blocks.len() as f64).collect::<Vec<_>>();
+	let mut order = (0..configurations.len()).collect::<Vec<_>>();
+	order.sort_by(|first, second| test.rank_sums[*first].total_cmp(&test.rank_sums[*second]).then(mean_lengths[*first].total_cmp(&mean_lengths[*second])));
+	let rows = order.iter().map(|index| FriedmanRow {
+		configuration: configurations[*index],
+		parameters: dataset.describe(configurations[*index]),
+		mean_rank: test.rank_sums[*index] / blocks.len() as f64,
+		mean_length: mean_lengths[*index],
+		differs_from_best: test.p_value < 0.05 && test.differ(order[0], *index),
+	}).collect::<Vec<_>>();
+	return Ok(FriedmanAnalysis { rows, test, block_count: blocks.len(), excluded });
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_util::record;
+
+	#[test]
+	fn repeated_seeds_pair_every_run_once() {
+		let records = [
+			record("a", 1.0, 1.0, 1, 10.0), record("a", 1.0, 1.0, 1, 11.0), record("a", 1.0, 1.0, 2, 12.0),
+			record("a", 2.0, 1.0, 1, 20.0), record("a", 2.0, 1.0, 2, 22.0),
+		];
+		let dataset = Dataset::new(&records);
+		assert_eq!(paired_lengths(dataset.runs(0, 0), dataset.runs(1, 0)), vec![(10.0, 20.0), (12.0, 22.0)]);
+		// the second run with seed 1 has nothing left to pair with
+		let analysis = friedman(&dataset, BlockKind::Runs).unwrap();
+		assert_eq!(analysis.block_count, 2);
+		assert_eq!(analysis.best(), 0);
+		assert_eq!(analysis.rows[0].mean_length, 11.0);
+		assert_eq!(analysis.rows[1].mean_length, 21.0);
+	}
+}
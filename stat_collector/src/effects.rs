@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+
+use ant_colony::ExperimentRecord;
+use clap::ValueEnum;
+
+use crate::analysis::parameter_key;
+
+#[derive(ValueEnum, Clone, Copy)]
+pub enum Response {
+	Relative, // percent above the shortest length found on the instance, so instances of any size weigh the same
+	Length,
+}
+
+// name and level of a run
+type FactorLevel = (&'static str, fn(&ExperimentRecord) -> String);
+
+// the parameters that can vary in a sweep, named like the summary columns
+const FACTORS: [FactorLevel; 10] = [
+	("variant", |record| record.variant.clone()),
+	("ants", |record| record.ant_count.to_string()),
+	("iterations", |record| optional_level(record.max_iterations)),
+	("time_limit", |record| optional_level(record.time_limit)),
+	("target_length", |record| optional_level(record.target_length)),
+	("stagnation_limit", |record| optional_level(record.stagnation_limit)),
+	("evap_coeff", |record| record.pheromone_evaporation_coefficient.to_string()),
+	("rand_chance", |record| record.random_choice_chance.to_string()),
+	("pher_weight", |record| record.pheromone_weight.to_string()),
+	("heur_weight", |record| record.heuristic_weight.to_string()),
+];
+
+fn optional_level<T: ToString>(value: Option<T>) -> String {
+	return value.map_or_else(|| "none".to_string(), |value| value.to_string());
+}
+
+pub struct Factor {
+	pub name: String,
+	pub levels: Vec<String>, // numeric levels ascending, others in the order they first appear
+}
+
+pub struct MainEffect {
+	pub factor: usize,
+	pub means: Vec<f64>, // by level
+	pub counts: Vec<usize>,
+	pub sum_of_squares: f64,
+}
+
+pub struct Interaction {
+	pub first: usize,
+	pub second: usize,
+	pub means: Vec<Vec<Option<f64>>>, // by level of the first factor, then of the second, `None` for combinations without runs
+	pub sum_of_squares: f64,
+}
+
+pub struct VarianceSource {
+	pub name: String,
+	pub degrees_of_freedom: usize,
+	pub sum_of_squares: f64,
+	pub share: f64, // of the total sum of squares
+	pub f_statistic: Option<f64>, // against the variation between runs with the same parameters, if there are repeated runs
+	pub p_value: Option<f64>,
+}
+
+pub struct EffectAnalysis {
+	pub response: Response,
+	pub run_count: usize,
+	pub instance_count: usize,
+	pub grand_mean: f64,
+	pub factors: Vec<Factor>,
+	pub main_effects: Vec<MainEffect>, // by factor
+	pub interactions: Vec<Interaction>, // largest sum of squares first
+	pub sources: Vec<VarianceSource>, // largest share first, the higher-order interactions and the runs last
+	pub balanced: bool, // the same number of runs for every combination of levels, otherwise the breakdown is approximate
+}
+
+// Main effects and two-way interactions of every parameter that takes more than one value, with the instance as one more
+// factor when there are several. The sums of squares are those of a full factorial design: exact for a complete grid with
+// the same number of runs everywhere, like `sweep` writes
+pub fn analyze(records: &[ExperimentRecord], response: Response) -> Result<EffectAnalysis, String> {
+	let mut shortest: HashMap<&str, f64> = HashMap::new();
+	for record in records {
+		let length = shortest.entry(record.instance.as_str()).or_insert(record.length);
+		*length = length.min(record.length);
+	}
+	let values = records.iter().map(|record| match response {
+		Response::Relative if shortest[record.instance.as_str()] > 0.0 => 100.0 * (record.length / shortest[record.instance.as_str()] - 1.0),
+		Response::Relative => 0.0,
+		Response::Length => record.length,
+	}).collect::<Vec<_>>();
+
+	let mut factors = Vec::new();
+	let mut levels: Vec<Vec<usize>> = Vec::new(); // level of every record, by factor
+	let instance_factor: FactorLevel = ("instance", |record| record.instance.clone());
+	for (name, level) in FACTORS.iter().chain(std::iter::once(&instance_factor)) {
+		let labels = records.iter().map(level).collect::<Vec<_>>();
+		let mut distinct = Vec::new();
+		for label in &labels {
+			if !distinct.contains(label) {
+				distinct.push(label.clone());
+			}
+		}
+		if distinct.len() < 2 {
+			continue;
+		}
+		if distinct.iter().all(|label| label.parse::<f64>().is_ok()) {
+			distinct.sort_by(|first, second| first.parse::<f64>().unwrap().total_cmp(&second.parse::<f64>().unwrap()));
+		}
+		levels.push(labels.iter().map(|label| distinct.iter().position(|level| level == label).unwrap()).collect());
+		factors.push(Factor { name: name.to_string(), levels: distinct });
+	}
+	if factors.is_empty() {
+		return Err("Every run has the same parameters and instance, there are no effects to analyze".to_string());
+	}
+
+	let run_count = records.len();
+	let grand_mean = values.iter().sum::<f64>() / run_count as f64;
+	let total_sum_of_squares = values.iter().map(|value| (value - grand_mean).powi(2)).sum::<f64>();
+
+	let main_effects = factors.iter().enumerate().map(|(factor, Factor { levels: labels, .. })| {
+		let (mut sums, mut counts) = (vec![0.0; labels.len()], vec![0; labels.len()]);
+		for (value, level) in values.iter().zip(&levels[factor]) {
+			sums[*level] += value;
+			counts[*level] += 1;
+		}
+		let means = sums.iter().zip(&counts).map(|(sum, count)| sum / *count as f64).collect::<Vec<_>>();
+		let sum_of_squares = means.iter().zip(&counts).map(|(mean, count)| *count as f64 * (mean - grand_mean).powi(2)).sum();
+		return MainEffect { factor, means, counts, sum_of_squares };
+	}).collect::<Vec<_>>();
+
+	let mut interactions = Vec::new();
+	for first in 0..factors.len() {
+		for second in first + 1..factors.len() {
+			let (rows, columns) = (factors[first].levels.len(), factors[second].levels.len());
+			let (mut sums, mut counts) = (vec![vec![0.0; columns]; rows], vec![vec![0; columns]; rows]);
+			for (index, value) in values.iter().enumerate() {
+				sums[levels[first][index]][levels[second][index]] += value;
+				counts[levels[first][index]][levels[second][index]] += 1;
+			}
+			let mut sum_of_squares = 0.0;
+			let mut means = vec![vec![None; columns]; rows];
+			for row in 0..rows {
+				for column in 0..columns {
+					if counts[row][column] == 0 {
+						continue;
+					}
+					let mean = sums[row][column] / counts[row][column] as f64;
+					let residual = mean - main_effects[first].means[row] - main_effects[second].means[column] + grand_mean;
+					sum_of_squares += counts[row][column] as f64 * residual * residual;
+					means[row][column] = Some(mean);
+				}
+			}
+			interactions.push(Interaction { first, second, means, sum_of_squares });
+		}
+	}
+	interactions.sort_by(|first, second| second.sum_of_squares.total_cmp(&first.sum_of_squares));
+
+	// runs with the same parameters on the same instance only differ by their random numbers
+	let mut cells: HashMap<(&str, String), (f64, usize)> = HashMap::new();
+	for (record, value) in records.iter().zip(&values) {
+		let cell = cells.entry((record.instance.as_str(), parameter_key(record))).or_insert((0.0, 0));
+		cell.0 += value;
+		cell.1 += 1;
+	}
+	let error_sum_of_squares = records.iter().zip(&values).map(|(record, value)| {
+		let (sum, count) = cells[&(record.instance.as_str(), parameter_key(record))];
+		return (value - sum / count as f64).powi(2);
+	}).sum::<f64>();
+	let error_degrees = run_count - cells.len();
+	let combination_count = factors.iter().map(|factor| factor.levels.len()).product::<usize>();
+	let first_count = cells.values().next().unwrap().1;
+	let balanced = cells.len() == combination_count && cells.values().all(|(_, count)| *count == first_count);
+
+	let error_mean_square = (error_degrees > 0 && error_sum_of_squares > 0.0).then(|| error_sum_of_squares / error_degrees as f64);
+	let source = |name: String, degrees_of_freedom: usize, sum_of_squares: f64| {
+		let f_statistic = error_mean_square.filter(|_| degrees_of_freedom > 0).map(|error| sum_of_squares / degrees_of_freedom as f64 / error);
+		return VarianceSource {
+			name,
+			degrees_of_freedom,
+			sum_of_squares,
+			share: if total_sum_of_squares > 0.0 { sum_of_squares / total_sum_of_squares } else { 0.0 },
+			f_statistic,
+			p_value: f_statistic.map(|statistic| ant_colony::stats::f_survival(statistic, degrees_of_freedom as f64, error_degrees as f64)),
+		};
+	};
+	let mut sources = main_effects.iter().map(|effect| source(factors[effect.factor].name.clone(), effect.means.len() - 1, effect.sum_of_squares)).collect::<Vec<_>>();
+	sources.extend(interactions.iter().map(|interaction| source(
+		format!("{} × {}", factors[interaction.first].name, factors[interaction.second].name),
+		(factors[interaction.first].levels.len() - 1) * (factors[interaction.second].levels.len() - 1),
+		interaction.sum_of_squares,
+	)));
+	sources.sort_by(|first, second| second.sum_of_squares.total_cmp(&first.sum_of_squares));
+	let explained_degrees = sources.iter().map(|source| source.degrees_of_freedom).sum::<usize>();
+	let explained = sources.iter().map(|source| source.sum_of_squares).sum::<f64>();
+	// whatever the grid leaves after the two-way interactions, unbalanced grids can make this slightly off
+	let higher_degrees = (cells.len() - 1).saturating_sub(explained_degrees);
+	if higher_degrees > 0 {
+		sources.push(source("higher-order interactions".to_string(), higher_degrees, (total_sum_of_squares - explained - error_sum_of_squares).max(0.0)));
+	}
+	sources.push(VarianceSource { f_statistic: None, p_value: None, ..source("between runs".to_string(), error_degrees, error_sum_of_squares) });
+
+	return Ok(EffectAnalysis {
+		response,
+		run_count,
+		instance_count: shortest.len(),
+		grand_mean,
+		factors,
+		main_effects,
+		interactions,
+		sources,
+		balanced,
+	});
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_util::{assert_close, record};
+
+	#[test]
+	fn balanced_grid_matches_two_way_anova() {
+		// two runs for every combination of three pheromone and two heuristic weights, like R's aov(length ~ pher * heur)
+		let cells = [((1.0, 1.0), [10.0, 12.0]), ((1.0, 2.0), [14.0, 15.0]), ((2.0, 1.0), [11.0, 13.0]), ((2.0, 2.0), [18.0, 20.0]), ((3.0, 1.0), [9.0, 10.0]), ((3.0, 2.0), [16.0, 19.0])];
+		let records = cells.iter().flat_map(|((pheromone, heuristic), lengths)| lengths.iter().enumerate().map(|(seed, length)| record("a", *pheromone, *heuristic, seed as u64, *length))).collect::<Vec<_>>();
+		let analysis = analyze(&records, Response::Length).unwrap();
+		assert!(analysis.balanced);
+		assert_close(analysis.grand_mean, 13.916667, 1e-6);
+		let source = |name: &str| analysis.sources.iter().find(|source| source.name == name).unwrap();
+		// name, degrees of freedom, sum of squares, F and p-value
+		for (name, degrees, sum_of_squares, statistic, p_value) in [
+			("pher_weight", 2, 16.166667, 4.217391, 0.071816),
+			("heur_weight", 1, 114.083333, 59.521739, 0.000249),
+			("pher_weight × heur_weight", 2, 11.166667, 2.913043, 0.130596),
+		] {
+			assert_eq!(source(name).degrees_of_freedom, degrees);
+			assert_close(source(name).sum_of_squares, sum_of_squares, 1e-6);
+			assert_close(source(name).f_statistic.unwrap(), statistic, 1e-6);
+			assert_close(source(name).p_value.unwrap(), p_value, 1e-6);
+		}
+		assert_eq!(source("between runs").degrees_of_freedom, 6);
+		assert_close(source("between runs").sum_of_squares, 11.5, 1e-9);
+		// nothing is left for higher-order interactions, and the parts add up to the total
+		assert_eq!(analysis.sources.len(), 4);
+		assert_close(analysis.sources.iter().map(|source| source.sum_of_squares).sum::<f64>(), 152.916667, 1e-6);
+		assert_close(analysis.sources.iter().map(|source| source.share).sum::<f64>(), 1.0, 1e-12);
+	}
+
+	#[test]
+	fn identical_parameters_have_no_effects() {
+		assert!(analyze(&[record("a", 1.0, 1.0, 0, 10.0), record("a", 1.0, 1.0, 1, 12.0)], Response::Length).is_err());
+	}
+}
@@ -1,21 +1,235 @@
-use std::os::unix::fs::MetadataExt;
-
-fn main() {
-	let header = "ants,iterations,evap_coeff,rand_chance,pher_weight,heur_weight,graph_name,shortest_route,longest_route,average_route,time_for_stat_run";
-	let gex = regex::Regex::new(r"File (.*): .*is (\d*\.\d*),.*is (\d*\.\d*)\. .*is (\d*\.\d*)").unwrap();
-
-	println!("{}", header);
-
-	for directory in glob::glob("./output_*/").unwrap() {
-		let directory = directory.unwrap();
-		let run_data = std::fs::read_to_string(format!("{}/run_data.csv", directory.display())).unwrap();
-		let run_data = run_data.lines().nth(1).unwrap();
-		let metadata = std::fs::metadata(format!("{}/statistics.txt", directory.display())).unwrap();
-		let runtime = metadata.ctime() as u64 - metadata.created().unwrap().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
-		let stat_data = std::fs::read_to_string(format!("{}/statistics.txt", directory.display())).unwrap().lines().map(|line| {
-			let captures = gex.captures(line).unwrap();
-			return format!("{},{},{},{},{},{}", run_data, &captures[1], &captures[3], &captures[2], &captures[4], runtime);
-		}).collect::<Vec<_>>().join("\n");
-		println!("{}", stat_data);
+#![allow(clippy::needless_return)]
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use analysis::{parameter_key, BlockKind, Dataset, PairedTest};
+use effects::Response;
+use report::ReportFormat;
+use ant_colony::{BatchRunData, ExperimentRecord};
+use clap::{Args, Parser, Subcommand};
+use serde::Serialize;
+use significance::Correction;
+
+mod analysis;
+mod effects;
+mod report;
+mod significance;
+#[cfg(test)]
+mod test_util;
+
+// Record files are CSV from `sweep` or `stats --records` and JSON from `stats --result`, picked by the extension.
+// Without a command the files are summarized, like before there were commands
+#[derive(Parser)]
+#[command(about = "Summaries and statistical comparisons of ant_colony run records", args_conflicts_with_subcommands = true, arg_required_else_help = true)]
+struct Cli {
+	#[command(subcommand)]
+	command: Option<Command>,
+	records: Vec<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+	#[command(about = "Print one row per parameter set and instance with the length statistics and timings")]
+	Summary(RecordArgs),
+	#[command(about = "Rank the parameter sets on every instance by their mean length")]
+	Rank(RecordArgs),
+	#[command(about = "Test every pair of parameter sets on every instance with Wilcoxon signed-rank and Mann-Whitney U tests")]
+	Compare(CompareArgs),
+	#[command(about = "Find the best parameter set with a Friedman test across instances and Conover's post-hoc test")]
+	Friedman(FriedmanArgs),
+	#[command(about = "Break the variation of the lengths down into main effects and two-way interactions of the parameters")]
+	Effects(EffectsArgs),
+}
+
+#[derive(Args)]
+struct RecordArgs {
+	#[arg(required = true)]
+	records: Vec<PathBuf>,
+}
+
+#[derive(Args)]
+struct CompareArgs {
+	#[command(flatten)]
+	input: RecordArgs,
+	#[arg(long, value_enum, default_value = "wilcoxon")]
+	test: PairedTest, // decides the `better` column, both tests are always printed
+	#[arg(long, value_enum, default_value = "holm")]
+	correction: Correction,
+	#[arg(long, default_value_t = 0.05)]
+	alpha: f64,
+}
+
+#[derive(Args)]
+struct FriedmanArgs {
+	#[command(flatten)]
+	input: RecordArgs,
+	#[arg(long, value_enum, default_value = "instances")]
+	blocks: BlockKind,
+}
+
+#[derive(Args)]
+struct EffectsArgs {
+	#[command(flatten)]
+	input: RecordArgs,
+	#[arg(long, value_enum, default_value = "relative")]
+	response: Response,
+	#[arg(long, value_enum, default_value = "markdown")]
+	format: ReportFormat,
+	#[arg(long)]
+	charts: Option<PathBuf>, // directory for the SVG charts, the report links them by this path
+	#[arg(short, long)]
+	output: Option<PathBuf>, // the report, stdout if not set
+}
+
+// One row per parameter set and instance, the first columns are the ones the old statistics.txt scraping produced
+#[derive(Serialize)]
+struct SummaryRow<'a> {
+	ants: usize,
+	iterations: Option<u32>,
+	evap_coeff: f64,
+	rand_chance: f64,
+	pher_weight: f64,
+	heur_weight: f64,
+	graph_name: &'a str,
+	shortest_route: f64,
+	longest_route: f64,
+	average_route: f64,
+	time_for_stat_run: f64, // seconds the solver spent iterating, summed over the runs
+	variant: &'a str,
+	time_limit: Option<f64>,
+	target_length: Option<f64>,
+	stagnation_limit: Option<u32>,
+	runs: usize,
+	std_dev: f64,
+	median: f64,
+	mean_run_seconds: f64,
+	mean_iteration_seconds: f64,
+	construction_seconds: f64, // per run
+	pheromone_update_seconds: f64, // per run
+}
+
+// groups of runs with the same parameters and instance, in the order they first appear
+fn group_records(records: &[ExperimentRecord]) -> Vec<Vec<&ExperimentRecord>> {
+	let mut groups: Vec<Vec<&ExperimentRecord>> = Vec::new();
+	let mut group_indices = HashMap::new();
+	for record in records {
+		let index = *group_indices.entry((record.instance.as_str(), parameter_key(record))).or_insert_with(|| {
+			groups.push(Vec::new());
+			return groups.len() - 1;
+		});
+		groups[index].push(record);
 	}
+	return groups;
+}
+
+fn read_records(paths: &[PathBuf]) -> Result<Vec<ExperimentRecord>, String> {
+	let mut records = Vec::new();
+	for path in paths {
+		records.extend(ant_colony::read_records_file(path).map_err(|error| format!("{}: {}", path.display(), error))?);
+	}
+	return Ok(records);
+}
+
+fn write_rows<T: Serialize>(rows: impl IntoIterator<Item = T>) -> Result<(), String> {
+	let mut writer = csv::Writer::from_writer(std::io::stdout().lock());
+	for row in rows {
+		writer.serialize(row).map_err(|error| format!("Could not write the table: {}", error))?;
+	}
+	return writer.flush().map_err(|error| format!("Could not write the table: {}", error));
+}
+
+fn summarize(records: &[ExperimentRecord]) -> Result<(), String> {
+	return write_rows(group_records(records).into_iter().map(|group| {
+		let first = group[0];
+		let runs = BatchRunData { runs: group.iter().map(|record| record.run_record()).collect(), ..BatchRunData::default() };
+		let summary = runs.summary(None);
+		return SummaryRow {
+			ants: first.ant_count,
+			iterations: first.max_iterations,
+			evap_coeff: first.pheromone_evaporation_coefficient,
+			rand_chance: first.random_choice_chance,
+			pher_weight: first.pheromone_weight,
+			heur_weight: first.heuristic_weight,
+			graph_name: &first.instance,
+			shortest_route: summary.min,
+			longest_route: summary.max,
+			average_route: summary.mean,
+			time_for_stat_run: group.iter().map(|record| record.runtime_seconds).sum(),
+			variant: &first.variant,
+			time_limit: first.time_limit,
+			target_length: first.target_length,
+			stagnation_limit: first.stagnation_limit,
+			runs: summary.run_count,
+			std_dev: summary.std_dev,
+			median: summary.median,
+			mean_run_seconds: summary.mean_runtime_seconds,
+			mean_iteration_seconds: summary.mean_iteration_seconds,
+			construction_seconds: summary.mean_construction_seconds,
+			pheromone_update_seconds: summary.mean_pheromone_update_seconds,
+		};
+	}));
+}
+
+fn run_friedman(records: &[ExperimentRecord], args: &FriedmanArgs) -> Result<(), String> {
+	let dataset = Dataset::new(records);
+	let analysis = analysis::friedman(&dataset, args.blocks)?;
+	if !analysis.excluded.is_empty() {
+		eprintln!("Left out parameter sets {} that didn't run on every instance", analysis.excluded.iter().map(usize::to_string).collect::<Vec<_>>().join(", "));
+	}
+	let block_name = match args.blocks {
+		BlockKind::Instances => "instances",
+		BlockKind::Runs => "runs",
+	};
+	eprintln!("Friedman test over {} {} and {} parameter sets: chi-squared {}, p-value {}", analysis.block_count, block_name, analysis.rows.len(), analysis.test.statistic, analysis.test.p_value);
+	let best = analysis.best();
+	if analysis.test.p_value < 0.05 {
+		let equivalent = analysis.equivalent_to_best();
+		eprintln!("Best parameter set {}: {}", best, dataset.describe(best));
+		match equivalent.is_empty() {
+			true => eprintln!("It is better than every other set"),
+			false => eprintln!("Not significantly worse than it: {}", equivalent.iter().map(usize::to_string).collect::<Vec<_>>().join(", ")),
+		}
+	} else {
+		eprintln!("The parameter sets don't differ significantly, set {} has the lowest mean rank: {}", best, dataset.describe(best));
+	}
+	return write_rows(analysis.rows);
+}
+
+fn run_effects(records: &[ExperimentRecord], args: &EffectsArgs) -> Result<(), String> {
+	let analysis = effects::analyze(records, args.response)?;
+	let charts = args.charts.as_ref().map(|directory| report::write_charts(&analysis, directory)).transpose()?;
+	let report = report::render(&analysis, args.format, charts.as_ref());
+	return match &args.output {
+		Some(path) => std::fs::write(path, report).map_err(|error| format!("{}: {}", path.display(), error)),
+		None => {
+			print!("{}", report);
+			Ok(())
+		},
+	};
+}
+
+fn main() -> ExitCode {
+	let cli = Cli::parse();
+	let result = match cli.command {
+		None => read_records(&cli.records).and_then(|records| summarize(&records)),
+		Some(Command::Summary(args)) => read_records(&args.records).and_then(|records| summarize(&records)),
+		Some(Command::Rank(args)) => read_records(&args.records).and_then(|records| write_rows(analysis::rank(&Dataset::new(&records)))),
+		Some(Command::Compare(args)) => read_records(&args.input.records).and_then(|records| {
+			if !(0.0..=1.0).contains(&args.alpha) {
+				return Err(format!("--alpha has to be between 0 and 1, got {}", args.alpha));
+			}
+			return write_rows(analysis::compare(&Dataset::new(&records), args.test, args.correction, args.alpha));
+		}),
+		Some(Command::Friedman(args)) => read_records(&args.input.records).and_then(|records| run_friedman(&records, &args)),
+		Some(Command::Effects(args)) => read_records(&args.input.records).and_then(|records| run_effects(&records, &args)),
+	};
+	return match result {
+		Ok(()) => ExitCode::SUCCESS,
+		Err(error) => {
+			eprintln!("{}", error);
+			ExitCode::FAILURE
+		},
+	};
 }
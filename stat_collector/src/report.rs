@@ -0,0 +1,294 @@
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+
+use crate::effects::{EffectAnalysis, Response};
+
+#[derive(ValueEnum, Clone, Copy)]
+pub enum ReportFormat {
+	Markdown,
+	Html,
+}
+
+// colors of the lines in interaction charts, repeated past the last one
+const PALETTE: [&str; 8] = ["#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#7f7f7f"];
+const PANEL_WIDTH: f64 = 260.0;
+const PANEL_HEIGHT: f64 = 200.0;
+const MARGIN: f64 = 50.0; // around a panel for its title, axis labels and ticks
+
+struct Table {
+	caption: String,
+	header: Vec<String>,
+	rows: Vec<Vec<String>>,
+}
+
+// The report has the variance breakdown, the marginal means of every factor and the cell means of every pair of factors.
+// `charts` are the files written by `write_charts`, linked below their tables
+pub fn render(analysis: &EffectAnalysis, format: ReportFormat, charts: Option<&Charts>) -> String {
+	let response_name = response_name(analysis.response);
+	let response_description = match analysis.response {
+		Response::Relative => "how many percent a run's length lies above the shortest length found on its instance",
+		Response::Length => "the length of the found tour",
+	};
+	let mut introduction = format!("{} runs on {} instance{}. The response is {}, {} on average.", analysis.run_count,
+		analysis.instance_count, if analysis.instance_count == 1 { "" } else { "s" }, response_description, format_value(analysis.grand_mean));
+	if !analysis.balanced {
+		introduction.push_str(" The runs don't form a complete grid with the same number of runs everywhere, so the breakdown is approximate.");
+	}
+
+	let breakdown = Table {
+		caption: "Variance breakdown, largest share first. F and p test every source against the variation between runs with the same parameters.".to_string(),
+		header: ["source", "df", "sum of squares", "share", "F", "p"].map(String::from).to_vec(),
+		rows: analysis.sources.iter().map(|source| vec![
+			source.name.clone(),
+			source.degrees_of_freedom.to_string(),
+			format_value(source.sum_of_squares),
+			format!("{:.1}%", 100.0 * source.share),
+			source.f_statistic.map_or_else(String::new, format_value),
+			source.p_value.map_or_else(String::new, format_p_value),
+		]).collect(),
+	};
+	let main_tables = analysis.main_effects.iter().map(|effect| {
+		let factor = &analysis.factors[effect.factor];
+		return Table {
+			caption: format!("Mean {} by {}", response_name, factor.name),
+			header: vec![factor.name.clone(), format!("mean {}", response_name), "runs".to_string()],
+			rows: factor.levels.iter().zip(&effect.means).zip(&effect.counts).map(|((level, mean), count)| vec![level.clone(), format_value(*mean), count.to_string()]).collect(),
+		};
+	}).collect::<Vec<_>>();
+	let interaction_tables = analysis.interactions.iter().map(|interaction| {
+		let (first, second) = (&analysis.factors[interaction.first], &analysis.factors[interaction.second]);
+		return Table {
+			caption: format!("Mean {} by {} (rows) and {} (columns)", response_name, first.name, second.name),
+			header: std::iter::once(format!("{} \\ {}", first.name, second.name)).chain(second.levels.iter().cloned()).collect(),
+			rows: first.levels.iter().zip(&interaction.means).map(|(level, means)| {
+				return std::iter::once(level.clone()).chain(means.iter().map(|mean| mean.map_or_else(String::new, format_value))).collect();
+			}).collect(),
+		};
+	}).collect::<Vec<_>>();
+
+	let mut sections: Vec<(&str, Vec<Table>, Vec<&Path>)> = vec![
+		("Variance breakdown", vec![breakdown], charts.map(|charts| vec![charts.variance.as_path()]).unwrap_or_default()),
+		("Main effects", main_tables, charts.map(|charts| vec![charts.main_effects.as_path()]).unwrap_or_default()),
+	];
+	if !interaction_tables.is_empty() {
+		sections.push(("Two-way interactions", interaction_tables, charts.map(|charts| charts.interactions.iter().map(PathBuf::as_path).collect()).unwrap_or_default()));
+	}
+	return match format {
+		ReportFormat::Markdown => render_markdown(&introduction, &sections),
+		ReportFormat::Html => render_html(&introduction, &sections),
+	};
+}
+
+fn render_markdown(introduction: &str, sections: &[(&str, Vec<Table>, Vec<&Path>)]) -> String {
+	let mut output = format!("# Parameter effects\n\n{}\n", introduction);
+	for (title, tables, charts) in sections {
+		output.push_str(&format!("\n## {}\n", title));
+		for table in tables {
+			output.push_str(&format!("\n{}\n\n", table.caption));
+			let escape = |cell: &String| cell.replace('|', "\\|");
+			output.push_str(&format!("| {} |\n", table.header.iter().map(escape).collect::<Vec<_>>().join(" | ")));
+			output.push_str(&format!("|{}\n", table.header.iter().enumerate().map(|(column, _)| if column == 0 { " --- |" } else { " ---: |" }).collect::<String>()));
+			for row in &table.rows {
+				output.push_str(&format!("| {} |\n", row.iter().map(escape).collect::<Vec<_>>().join(" | ")));
+			}
+		}
+		for chart in charts {
+			output.push_str(&format!("\n![{}]({})\n", title, chart.display()));
+		}
+	}
+	return output;
+}
+
+fn render_html(introduction: &str, sections: &[(&str, Vec<Table>, Vec<&Path>)]) -> String {
+	let mut output = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Parameter effects</title>\n<style>\n");
+	output.push_str("body { font-family: sans-serif; }\ntable { border-collapse: collapse; margin-bottom: 1em; }\n");
+	output.push_str("th, td { border: 1px solid #ccc; padding: 2px 8px; }\ntd { text-align: right; }\ntd:first-child { text-align: left; }\n");
+	output.push_str("</style>\n</head>\n<body>\n<h1>Parameter effects</h1>\n");
+	output.push_str(&format!("<p>{}</p>\n", escape_html(introduction)));
+	for (title, tables, charts) in sections {
+		output.push_str(&format!("<h2>{}</h2>\n", escape_html(title)));
+		for table in tables {
+			output.push_str(&format!("<table>\n<caption>{}</caption>\n<tr>", escape_html(&table.caption)));
+			for cell in &table.header {
+				output.push_str(&format!("<th>{}</th>", escape_html(cell)));
+			}
+			output.push_str("</tr>\n");
+			for row in &table.rows {
+				output.push_str("<tr>");
+				for cell in row {
+					output.push_str(&format!("<td>{}</td>", escape_html(cell)));
+				}
+				output.push_str("</tr>\n");
+			}
+			output.push_str("</table>\n");
+		}
+		for chart in charts {
+			output.push_str(&format!("<p><img src=\"{}\" alt=\"{}\"></p>\n", escape_html(&chart.display().to_string()), escape_html(title)));
+		}
+	}
+	output.push_str("</body>\n</html>\n");
+	return output;
+}
+
+pub struct Charts {
+	pub variance: PathBuf,
+	pub main_effects: PathBuf,
+	pub interactions: Vec<PathBuf>, // in the order of the analysis' interactions
+}
+
+// Writes `variance.svg` with the share of every source, `main_effects.svg` with one panel per factor and an
+// `interaction_<first>_<second>.svg` for every pair of factors into `directory`
+pub fn write_charts(analysis: &EffectAnalysis, directory: &Path) -> Result<Charts, String> {
+	std::fs::create_dir_all(directory).map_err(|error| format!("{}: {}", directory.display(), error))?;
+	let write = |name: String, contents: String| {
+		let path = directory.join(name);
+		std::fs::write(&path, contents).map_err(|error| format!("{}: {}", path.display(), error))?;
+		return Ok::<_, String>(path);
+	};
+	let response_name = response_name(analysis.response);
+
+	// horizontal bars, one per source
+	let label_width = 260.0;
+	let bar_width = 300.0;
+	let row_height = 22.0;
+	let height = 40.0 + row_height * analysis.sources.len() as f64;
+	let mut variance = svg_start(label_width + bar_width + 80.0, height);
+	variance.push_str(&format!("<text x=\"10\" y=\"20\" font-weight=\"bold\">Share of the variance in {}</text>\n", response_name));
+	for (index, source) in analysis.sources.iter().enumerate() {
+		let y = 30.0 + row_height * index as f64;
+		variance.push_str(&format!("<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>\n", label_width - 8.0, y + 15.0, escape_html(&source.name)));
+		variance.push_str(&format!("<rect x=\"{}\" y=\"{}\" width=\"{:.1}\" height=\"{}\" fill=\"{}\"/>\n", label_width, y + 3.0, bar_width * source.share, row_height - 6.0, PALETTE[0]));
+		variance.push_str(&format!("<text x=\"{:.1}\" y=\"{}\">{:.1}%</text>\n", label_width + bar_width * source.share + 5.0, y + 15.0, 100.0 * source.share));
+	}
+	variance.push_str("</svg>\n");
+	let variance = write("variance.svg".to_string(), variance)?;
+
+	// main effects share the vertical axis so their sizes can be compared at a glance
+	let all_means = analysis.main_effects.iter().flat_map(|effect| effect.means.iter().copied());
+	let axis = Axis::new(all_means.clone().fold(f64::INFINITY, f64::min), all_means.fold(f64::NEG_INFINITY, f64::max));
+	let columns = analysis.main_effects.len().min(3);
+	let rows = analysis.main_effects.len().div_ceil(3);
+	let mut main_effects = svg_start(columns as f64 * (PANEL_WIDTH + 2.0 * MARGIN), rows as f64 * (PANEL_HEIGHT + 2.0 * MARGIN));
+	for (index, effect) in analysis.main_effects.iter().enumerate() {
+		let factor = &analysis.factors[effect.factor];
+		let origin = ((index % 3) as f64 * (PANEL_WIDTH + 2.0 * MARGIN) + MARGIN, (index / 3) as f64 * (PANEL_HEIGHT + 2.0 * MARGIN) + MARGIN);
+		let series = [(None, effect.means.iter().copied().map(Some).collect::<Vec<_>>())];
+		main_effects.push_str(&panel(origin, &factor.name, &factor.levels, &series, &axis, response_name));
+	}
+	main_effects.push_str("</svg>\n");
+	let main_effects = write("main_effects.svg".to_string(), main_effects)?;
+
+	let mut interactions = Vec::new();
+	for interaction in &analysis.interactions {
+		let (first, second) = (&analysis.factors[interaction.first], &analysis.factors[interaction.second]);
+		let values = interaction.means.iter().flatten().flatten().copied();
+		let axis = Axis::new(values.clone().fold(f64::INFINITY, f64::min), values.fold(f64::NEG_INFINITY, f64::max));
+		// one line per level of the second factor across the levels of the first
+		let series = second.levels.iter().enumerate().map(|(column, level)| {
+			return (Some(format!("{} = {}", second.name, level)), interaction.means.iter().map(|means| means[column]).collect::<Vec<_>>());
+		}).collect::<Vec<_>>();
+		let legend_width = 180.0;
+		let mut chart = svg_start(PANEL_WIDTH + 2.0 * MARGIN + legend_width, (PANEL_HEIGHT + 2.0 * MARGIN).max(MARGIN + 20.0 * series.len() as f64));
+		chart.push_str(&panel((MARGIN, MARGIN), &format!("{} × {}", first.name, second.name), &first.levels, &series, &axis, response_name));
+		chart.push_str("</svg>\n");
+		interactions.push(write(format!("interaction_{}_{}.svg", first.name, second.name), chart)?);
+	}
+	return Ok(Charts { variance, main_effects, interactions });
+}
+
+fn svg_start(width: f64, height: f64) -> String {
+	return format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\" font-family=\"sans-serif\" font-size=\"12\">\n\
+		<rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n", width, height);
+}
+
+// Vertical axis with round tick values covering the data
+struct Axis {
+	min: f64,
+	max: f64,
+	step: f64,
+}
+
+impl Axis {
+	fn new(min: f64, max: f64) -> Self {
+		let (min, max) = if max > min { (min, max) } else { (min - 1.0, max + 1.0) };
+		let rough_step = (max - min) / 4.0;
+		let magnitude = 10f64.powf(rough_step.log10().floor());
+		let step = [1.0, 2.0, 5.0, 10.0].into_iter().map(|factor| factor * magnitude).find(|step| *step >= rough_step).unwrap();
+		return Self { min: (min / step).floor() * step, max: (max / step).ceil() * step, step };
+	}
+
+	// distance from the top of the panel
+	fn position(&self, value: f64) -> f64 {
+		return PANEL_HEIGHT * (self.max - value) / (self.max - self.min);
+	}
+}
+
+// A line chart at `origin` with one point per level, series with a label get a legend right of the panel
+fn panel(origin: (f64, f64), title: &str, levels: &[String], series: &[(Option<String>, Vec<Option<f64>>)], axis: &Axis, axis_label: &str) -> String {
+	let (left, top) = origin;
+	let mut output = format!("<g transform=\"translate({},{})\">\n", left, top);
+	output.push_str(&format!("<text x=\"{}\" y=\"-15\" text-anchor=\"middle\" font-weight=\"bold\">{}</text>\n", PANEL_WIDTH / 2.0, escape_html(title)));
+	output.push_str(&format!("<rect width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"#999\"/>\n", PANEL_WIDTH, PANEL_HEIGHT));
+	let tick_count = ((axis.max - axis.min) / axis.step).round() as usize;
+	for tick in 0..=tick_count {
+		let value = axis.min + tick as f64 * axis.step;
+		let y = axis.position(value);
+		output.push_str(&format!("<line x1=\"0\" x2=\"{}\" y1=\"{:.1}\" y2=\"{:.1}\" stroke=\"#eee\"/>\n", PANEL_WIDTH, y, y));
+		output.push_str(&format!("<text x=\"-5\" y=\"{:.1}\" text-anchor=\"end\" dominant-baseline=\"middle\">{}</text>\n", y, format_tick(value, axis.step)));
+	}
+	output.push_str(&format!("<text transform=\"translate({},{}) rotate(-90)\" text-anchor=\"middle\" font-size=\"10\">{}</text>\n", -MARGIN + 10.0, PANEL_HEIGHT / 2.0, escape_html(axis_label)));
+	let x = |level: usize| PANEL_WIDTH * (level as f64 + 0.5) / levels.len() as f64;
+	for (level, label) in levels.iter().enumerate() {
+		output.push_str(&format!("<text x=\"{:.1}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n", x(level), PANEL_HEIGHT + 16.0, escape_html(label)));
+	}
+	for (index, (label, values)) in series.iter().enumerate() {
+		let color = PALETTE[index % PALETTE.len()];
+		// combinations without runs break the line
+		let mut path = String::new();
+		let mut pen_down = false;
+		for (level, value) in values.iter().enumerate() {
+			match value {
+				Some(value) => {
+					path.push_str(&format!("{}{:.1},{:.1} ", if pen_down { "L" } else { "M" }, x(level), axis.position(*value)));
+					output.push_str(&format!("<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"3\" fill=\"{}\"/>\n", x(level), axis.position(*value), color));
+					pen_down = true;
+				},
+				None => pen_down = false,
+			}
+		}
+		output.push_str(&format!("<path d=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\"/>\n", path.trim_end(), color));
+		if let Some(label) = label {
+			let y = 10.0 + 20.0 * index as f64;
+			output.push_str(&format!("<line x1=\"{}\" x2=\"{}\" y1=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"2\"/>\n", PANEL_WIDTH + 20.0, PANEL_WIDTH + 40.0, y, y, color));
+			output.push_str(&format!("<text x=\"{}\" y=\"{}\" dominant-baseline=\"middle\">{}</text>\n", PANEL_WIDTH + 45.0, y, escape_html(label)));
+		}
+	}
+	output.push_str("</g>\n");
+	return output;
+}
+
+fn response_name(response: Response) -> &'static str {
+	return match response {
+		Response::Relative => "% above shortest",
+		Response::Length => "length",
+	};
+}
+
+fn format_value(value: f64) -> String {
+	return format!("{:.3}", value);
+}
+
+fn format_p_value(p_value: f64) -> String {
+	return if p_value < 0.0001 { "< 0.0001".to_string() } else { format!("{:.4}", p_value) };
+}
+
+// as many decimals as the tick step needs
+fn format_tick(value: f64, step: f64) -> String {
+	let decimals = (-step.log10().floor()).max(0.0) as usize;
+	return format!("{:.*}", decimals, value);
+}
+
+fn escape_html(text: &str) -> String {
+	return text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;");
+}
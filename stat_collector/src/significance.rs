@@ -0,0 +1,214 @@
+use ant_colony::stats::{average_ranks, chi_squared_survival};
+use clap::ValueEnum;
+
+// past these sizes, or with ties, the p-values come from the normal approximation
+const MAX_EXACT_PAIRS: usize = 30;
+const MAX_EXACT_SAMPLE: usize = 20;
+
+pub struct RankTest {
+	pub statistic: f64,
+	pub p_value: f64, // two-sided
+	pub first_lower: bool, // whether the first sample tends to the shorter lengths
+}
+
+// Wilcoxon signed-rank test of `first - second` over paired results, pairs with equal results are dropped.
+// The statistic is the rank sum of the pairs where the first result is longer
+pub fn wilcoxon_signed_rank(pairs: &[(f64, f64)]) -> RankTest {
+	let differences = pairs.iter().map(|(first, second)| first - second).filter(|difference| *difference != 0.0).collect::<Vec<_>>();
+	let n = differences.len();
+	let ranks = average_ranks(&differences.iter().map(|difference| difference.abs()).collect::<Vec<_>>());
+	let statistic = differences.iter().zip(&ranks).filter(|(difference, _)| **difference > 0.0).map(|(_, rank)| rank).sum::<f64>();
+	let mean = (n * (n + 1)) as f64 / 4.0;
+	let first_lower = statistic < mean;
+	if n == 0 {
+		return RankTest { statistic, p_value: 1.0, first_lower };
+	}
+	let ties = tie_sizes(&ranks);
+	if n <= MAX_EXACT_PAIRS && ties.is_empty() {
+		// every subset of the ranks 1..=n is an equally likely set of positive differences
+		let mut counts = vec![0.0; n * (n + 1) / 2 + 1];
+		counts[0] = 1.0;
+		for rank in 1..=n {
+			for sum in (rank..counts.len()).rev() {
+				counts[sum] += counts[sum - rank];
+			}
+		}
+		return RankTest { statistic, p_value: exact_two_sided(&counts, statistic as usize), first_lower };
+	}
+	let tie_correction = ties.iter().map(|size| size.powi(3) - size).sum::<f64>() / 48.0;
+	let variance = (n * (n + 1) * (2 * n + 1)) as f64 / 24.0 - tie_correction;
+	return RankTest { statistic, p_value: normal_two_sided(statistic - mean, variance), first_lower };
+}
+
+// Mann-Whitney U test of two independent samples. U counts the pairs where the first result is longer, ties count half
+pub fn mann_whitney_u(first: &[f64], second: &[f64]) -> RankTest {
+	let (n1, n2) = (first.len(), second.len());
+	let ranks = average_ranks(&first.iter().chain(second).copied().collect::<Vec<_>>());
+	let rank_sum = ranks[..n1].iter().sum::<f64>();
+	let statistic = rank_sum - (n1 * (n1 + 1)) as f64 / 2.0;
+	let mean = (n1 * n2) as f64 / 2.0;
+	let first_lower = statistic < mean;
+	if n1 == 0 || n2 == 0 {
+		return RankTest { statistic, p_value: 1.0, first_lower };
+	}
+	let ties = tie_sizes(&ranks);
+	if n1.max(n2) <= MAX_EXACT_SAMPLE && ties.is_empty() {
+		// counts[size][sum]: ways to pick `size` of the ranks seen so far with that rank sum
+		let total = n1 + n2;
+		let max_sum = total * (total + 1) / 2;
+		let mut counts = vec![vec![0.0; max_sum + 1]; n1 + 1];
+		counts[0][0] = 1.0;
+		for rank in 1..=total {
+			for size in (1..=n1.min(rank)).rev() {
+				for sum in (rank..=max_sum).rev() {
+					counts[size][sum] += counts[size - 1][sum - rank];
+				}
+			}
+		}
+		let offset = n1 * (n1 + 1) / 2;
+		return RankTest { statistic, p_value: exact_two_sided(&counts[n1][offset..=offset + n1 * n2], statistic as usize), first_lower };
+	}
+	let total = (n1 + n2) as f64;
+	let tie_correction = ties.iter().map(|size| size.powi(3) - size).sum::<f64>() / (total * (total - 1.0));
+	let variance = (n1 * n2) as f64 / 12.0 * (total + 1.0 - tie_correction);
+	return RankTest { statistic, p_value: normal_two_sided(statistic - mean, variance), first_lower };
+}
+
+// sizes of the groups of tied ranks, ties share the same average rank
+fn tie_sizes(ranks: &[f64]) -> Vec<f64> {
+	let mut sorted = ranks.to_vec();
+	sorted.sort_by(f64::total_cmp);
+	return sorted.chunk_by(|first, second| first == second).map(|tie| tie.len() as f64).filter(|size| *size > 1.0).collect();
+}
+
+// `counts` holds how often each statistic value occurs under the null hypothesis
+fn exact_two_sided(counts: &[f64], statistic: usize) -> f64 {
+	let total = counts.iter().sum::<f64>();
+	let lower = counts[..=statistic].iter().sum::<f64>() / total;
+	let upper = counts[statistic..].iter().sum::<f64>() / total;
+	return (2.0 * lower.min(upper)).min(1.0);
+}
+
+// normal approximation with continuity correction
+fn normal_two_sided(deviation: f64, variance: f64) -> f64 {
+	if variance <= 0.0 {
+		return 1.0;
+	}
+	let z = ((deviation.abs() - 0.5).max(0.0)) / variance.sqrt();
+	// a squared standard normal is chi-squared with one degree of freedom
+	return chi_squared_survival(z * z, 1.0);
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+pub enum Correction {
+	Holm,
+	Bonferroni,
+	BenjaminiHochberg, // controls the false discovery rate instead of the family-wise error
+	None,
+}
+
+// adjusts a family of p-values for multiple comparisons, missing ones don't count towards the family
+pub fn adjust_p_values(p_values: &[Option<f64>], correction: Correction) -> Vec<Option<f64>> {
+	let mut order = (0..p_values.len()).filter(|index| p_values[*index].is_some()).collect::<Vec<_>>();
+	order.sort_by(|first, second| p_values[*first].unwrap().total_cmp(&p_values[*second].unwrap()));
+	let m = order.len() as f64;
+	let mut adjusted = vec![None; p_values.len()];
+	match correction {
+		Correction::None => return p_values.to_vec(),
+		Correction::Bonferroni => {
+			for index in order {
+				adjusted[index] = Some((p_values[index].unwrap() * m).min(1.0));
+			}
+		},
+		Correction::Holm => {
+			let mut running_max: f64 = 0.0;
+			for (position, index) in order.into_iter().enumerate() {
+				running_max = running_max.max((p_values[index].unwrap() * (m - position as f64)).min(1.0));
+				adjusted[index] = Some(running_max);
+			}
+		},
+		Correction::BenjaminiHochberg => {
+			let mut running_min: f64 = 1.0;
+			for (position, index) in order.into_iter().enumerate().rev() {
+				running_min = running_min.min(p_values[index].unwrap() * m / (position + 1) as f64);
+				adjusted[index] = Some(running_min);
+			}
+		},
+	}
+	return adjusted;
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_util::assert_close;
+
+	#[test]
+	#[allow(clippy::approx_constant)] // 3.14 is a score, not pi
+	fn exact_signed_rank_matches_r() {
+		// the depression scores of R's wilcox.test example: V = 40, two-sided p-value = 0.03906
+		let first = [1.83, 0.50, 1.62, 2.48, 1.68, 1.88, 1.55, 3.06, 1.30];
+		let second = [0.878, 0.647, 0.598, 2.05, 1.06, 1.29, 1.06, 3.14, 1.29];
+		let test = wilcoxon_signed_rank(&first.into_iter().zip(second).collect::<Vec<_>>());
+		assert_eq!(test.statistic, 40.0);
+		assert_close(test.p_value, 0.0390625, 1e-12);
+		assert!(!test.first_lower);
+	}
+
+	#[test]
+	fn signed_rank_with_ties_uses_the_normal_approximation() {
+		// wilcox.test(first, second, paired = TRUE, correct = TRUE): V = 26.5, p-value = 0.04068, the equal pair is dropped
+		let first = [15.0, 20.0, 31.0, 40.0, 22.0, 33.0, 50.0, 10.0];
+		let second = [10.0, 25.0, 20.0, 30.0, 12.0, 23.0, 30.0, 10.0];
+		let test = wilcoxon_signed_rank(&first.into_iter().zip(second).collect::<Vec<_>>());
+		assert_eq!(test.statistic, 26.5);
+		assert_close(test.p_value, 0.0406849, 1e-6);
+		assert_eq!(wilcoxon_signed_rank(&[(1.0, 1.0)]).p_value, 1.0);
+	}
+
+	#[test]
+	fn exact_rank_sum_matches_r() {
+		// R's wilcox.test example of permeability constants: W = 35, two-sided p-value = 0.2544
+		let first = [0.80, 0.83, 1.89, 1.04, 1.45, 1.38, 1.91, 1.64, 0.73, 1.46];
+		let second = [1.15, 0.88, 0.90, 0.74, 1.21];
+		let test = mann_whitney_u(&first, &second);
+		assert_eq!(test.statistic, 35.0);
+		assert_close(test.p_value, 0.2544122544, 1e-9);
+		assert!(!test.first_lower);
+		// swapping the samples mirrors the statistic and keeps the p-value
+		let swapped = mann_whitney_u(&second, &first);
+		assert_eq!(swapped.statistic, 15.0);
+		assert_close(swapped.p_value, test.p_value, 1e-12);
+		assert!(swapped.first_lower);
+	}
+
+	#[test]
+	fn rank_sum_with_ties_uses_the_normal_approximation() {
+		// wilcox.test(first, second, correct = TRUE): W = 16, p-value = 0.3013
+		let test = mann_whitney_u(&[1.0, 2.0, 2.0, 3.0, 4.0, 5.0, 6.0], &[2.0, 3.0, 3.0, 4.0, 5.0, 7.0, 9.0]);
+		assert_eq!(test.statistic, 16.0);
+		assert_close(test.p_value, 0.3013119, 1e-6);
+		assert_eq!(mann_whitney_u(&[], &[1.0]).p_value, 1.0);
+	}
+
+	fn assert_all_close(actual: &[Option<f64>], expected: &[Option<f64>]) {
+		assert_eq!(actual.len(), expected.len());
+		for (actual, expected) in actual.iter().zip(expected) {
+			match (actual, expected) {
+				(Some(actual), Some(expected)) => assert_close(*actual, *expected, 1e-12),
+				_ => assert_eq!(actual, expected),
+			}
+		}
+	}
+
+	// p.adjust(c(0.01, 0.04, 0.03, 0.005, 0.2), method), the missing value is left out of the family
+	const P_VALUES: [Option<f64>; 6] = [Some(0.01), Some(0.04), None, Some(0.03), Some(0.005), Some(0.2)];
+
+	#[test]
+	fn corrections_match_p_adjust() {
+		assert_all_close(&adjust_p_values(&P_VALUES, Correction::Holm), &[Some(0.04), Some(0.09), None, Some(0.09), Some(0.025), Some(0.2)]);
+		assert_all_close(&adjust_p_values(&P_VALUES, Correction::Bonferroni), &[Some(0.05), Some(0.2), None, Some(0.15), Some(0.025), Some(1.0)]);
+		assert_all_close(&adjust_p_values(&P_VALUES, Correction::BenjaminiHochberg), &[Some(0.025), Some(0.05), None, Some(0.05), Some(0.025), Some(0.2)]);
+		assert_all_close(&adjust_p_values(&P_VALUES, Correction::None), &P_VALUES);
+	}
+}
@@ -0,0 +1,36 @@
+use ant_colony::ExperimentRecord;
+
+// Helpers shared by the tests of every module
+
+pub fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+	assert!((actual - expected).abs() <= tolerance, "expected {} within {}, got {}", expected, tolerance, actual);
+}
+
+// a run that only differs from the others by its instance, its two weights, its seed and its length
+pub fn record(instance: &str, pheromone_weight: f64, heuristic_weight: f64, seed: u64, length: f64) -> ExperimentRecord {
+	return ExperimentRecord {
+		set: 0,
+		instance: instance.to_string(),
+		variant: "ant-system".to_string(),
+		ant_count: 10,
+		max_iterations: Some(100),
+		time_limit: None,
+		target_length: None,
+		stagnation_limit: None,
+		pheromone_evaporation_coefficient: 0.5,
+		random_choice_chance: 0.0,
+		pheromone_weight,
+		heuristic_weight,
+		run: 0,
+		thread: 0,
+		seed,
+		length,
+		best_iteration: 0,
+		iterations: 100,
+		runtime_seconds: 0.0,
+		mean_iteration_seconds: 0.0,
+		construction_seconds: 0.0,
+		best_update_seconds: 0.0,
+		pheromone_update_seconds: 0.0,
+	};
+}